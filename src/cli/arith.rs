@@ -0,0 +1,261 @@
+/// How deeply variables whose values are themselves expressions are
+/// followed.
+const MAX_DEPTH: usize = 16;
+
+/// Evaluates an arithmetic expression, as assignments to integer variables
+/// and array subscripts are. It has C's integer operators without
+/// assignment. `lookup` gives the value of a variable, which is evaluated
+/// in turn; unset and empty variables count as 0.
+pub fn evaluate(expr: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<i64, String> {
+    evaluate_at(expr, lookup, 0)
+}
+
+fn evaluate_at(
+    expr: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    depth: usize,
+) -> Result<i64, String> {
+    if depth > MAX_DEPTH {
+        return Err(format!(
+            "{}: expression recursion level exceeded",
+            expr.trim()
+        ));
+    }
+    let mut parser = Parser {
+        expr,
+        pos: 0,
+        lookup,
+        depth,
+    };
+    parser.skip_blanks();
+    if parser.pos == expr.len() {
+        return Ok(0);
+    }
+    let value = parser.ternary()?;
+    parser.skip_blanks();
+    if parser.pos < expr.len() {
+        return Err(parser.error("syntax error in expression"));
+    }
+    Ok(value)
+}
+
+/// Binary operators from the loosest binding to the tightest, below the
+/// ternary operator.
+const LEVELS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+struct Parser<'a> {
+    expr: &'a str,
+    pos: usize,
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.expr[self.pos..]
+    }
+
+    fn skip_blanks(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!(
+            "{}: {} (error token is \"{}\")",
+            self.expr.trim(),
+            msg,
+            self.rest().trim()
+        )
+    }
+
+    /// Consumes `op` if it comes next, and is not the start of a longer
+    /// operator such as `&&` for `&`.
+    fn eat(&mut self, op: &str) -> bool {
+        self.skip_blanks();
+        let rest = self.rest();
+        if !rest.starts_with(op) {
+            return false;
+        }
+        let longer = ["||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "**"];
+        if op.len() == 1
+            && longer
+                .iter()
+                .any(|l| l.starts_with(op) && rest.starts_with(l))
+        {
+            return false;
+        }
+        self.pos += op.len();
+        true
+    }
+
+    fn ternary(&mut self) -> Result<i64, String> {
+        let cond = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let then = self.ternary()?;
+        if !self.eat(":") {
+            return Err(self.error("`:' expected for conditional expression"));
+        }
+        let otherwise = self.ternary()?;
+        Ok(if cond != 0 { then } else { otherwise })
+    }
+
+    fn binary(&mut self, level: usize) -> Result<i64, String> {
+        if level == LEVELS.len() {
+            return self.power();
+        }
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            for op in LEVELS[level] {
+                if self.eat(op) {
+                    let right = self.binary(level + 1)?;
+                    left = self.apply(op, left, right)?;
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn apply(&self, op: &str, a: i64, b: i64) -> Result<i64, String> {
+        Ok(match op {
+            "||" => i64::from(a != 0 || b != 0),
+            "&&" => i64::from(a != 0 && b != 0),
+            "|" => a | b,
+            "^" => a ^ b,
+            "&" => a & b,
+            "==" => i64::from(a == b),
+            "!=" => i64::from(a != b),
+            "<=" => i64::from(a <= b),
+            ">=" => i64::from(a >= b),
+            "<" => i64::from(a < b),
+            ">" => i64::from(a > b),
+            "<<" => a.wrapping_shl(b as u32),
+            ">>" => a.wrapping_shr(b as u32),
+            "+" => a.wrapping_add(b),
+            "-" => a.wrapping_sub(b),
+            "*" => a.wrapping_mul(b),
+            _ if b == 0 => return Err(self.error("division by 0")),
+            "/" => a.wrapping_div(b),
+            _ => a.wrapping_rem(b),
+        })
+    }
+
+    /// `**`, which binds to the right.
+    fn power(&mut self) -> Result<i64, String> {
+        let base = self.unary()?;
+        if !self.eat("**") {
+            return Ok(base);
+        }
+        let exp = self.power()?;
+        if exp < 0 {
+            return Err(self.error("exponent less than 0"));
+        }
+        Ok(base.wrapping_pow(exp.min(u32::MAX as i64) as u32))
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        if self.eat("!") {
+            return Ok(i64::from(self.unary()? == 0));
+        }
+        if self.eat("~") {
+            return Ok(!self.unary()?);
+        }
+        if self.eat("-") {
+            return Ok(self.unary()?.wrapping_neg());
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<i64, String> {
+        self.skip_blanks();
+        if self.eat("(") {
+            let value = self.ternary()?;
+            if !self.eat(")") {
+                return Err(self.error("missing `)'"));
+            }
+            return Ok(value);
+        }
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("syntax error: operand expected"));
+        }
+        let word = &rest[..len];
+        let value = if word.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_number(word).ok_or_else(|| self.error("value too great for base"))?
+        } else {
+            let value = (self.lookup)(word).unwrap_or_default();
+            evaluate_at(&value, self.lookup, self.depth + 1)?
+        };
+        self.pos += len;
+        Ok(value)
+    }
+}
+
+/// A decimal number, or hexadecimal after `0x`, or octal after `0`.
+fn parse_number(word: &str) -> Option<i64> {
+    if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).ok();
+    }
+    if word.len() > 1 && word.starts_with('0') {
+        return i64::from_str_radix(&word[1..], 8).ok();
+    }
+    word.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str) -> Result<i64, String> {
+        let lookup = |name: &str| match name {
+            "x" => Some("6".to_string()),
+            "y" => Some("x * 2".to_string()),
+            "loop" => Some("loop".to_string()),
+            _ => None,
+        };
+        evaluate(expr, &lookup)
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(eval(" 1 + 2 * 3 "), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3 - -1"), Ok(10));
+        assert_eq!(eval("2 ** 3 ** 2"), Ok(512));
+        assert_eq!(eval("7 / 2 + 7 % 2"), Ok(4));
+        assert_eq!(eval("x > 5 && !0 ? y : 1"), Ok(12));
+        assert_eq!(eval("1 << 4 | 0x0f & 010"), Ok(24));
+        assert_eq!(eval("unset + 1"), Ok(1));
+        assert_eq!(eval(""), Ok(0));
+        assert_eq!(
+            eval("1 +").unwrap_err(),
+            "1 +: syntax error: operand expected (error token is \"\")"
+        );
+        assert_eq!(
+            eval("x / 0").unwrap_err(),
+            "x / 0: division by 0 (error token is \"\")"
+        );
+        assert!(eval("1 2").is_err());
+        assert!(eval("loop").is_err());
+        assert!(eval("09").is_err());
+    }
+}
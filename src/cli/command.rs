@@ -1,45 +1,333 @@
+use crate::cli::dirs::DirStack;
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::hash::HashTable;
+use crate::cli::jobs::JobTable;
+use crate::cli::options::ShellOptions;
+use crate::cli::params::Params;
+use crate::cli::traps::TrapTable;
+use crate::cli::vars::Variables;
 use std::cell::RefCell;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io;
-use std::io::Write;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+#[derive(Clone)]
 pub enum IoHandle {
     Stdout,
     Stderr,
     Stdin,
-    File(Rc<RefCell<File>>),
+    /// A file or pipe, written through a buffer; see `impl Write`.
+    File(Rc<RefCell<BufWriter<File>>>),
+    /// In-memory output, for capturing what an embedded shell writes.
+    /// Reads from it see end of input.
+    Buffer(Rc<RefCell<Vec<u8>>>),
     Null,
 }
 
 impl IoHandle {
+    pub fn file(file: File) -> Self {
+        IoHandle::File(Rc::new(RefCell::new(BufWriter::new(file))))
+    }
+
+    /// Writes a line straight away, as the shell does for its messages.
     pub fn write_line(&mut self, msg: &str) -> io::Result<()> {
+        writeln!(self, "{}", msg)?;
+        self.flush()
+    }
+
+    /// The file descriptor behind the handle, if any.
+    pub fn raw_fd(&self) -> Option<RawFd> {
+        match self {
+            IoHandle::Stdin => Some(libc::STDIN_FILENO),
+            IoHandle::Stdout => Some(libc::STDOUT_FILENO),
+            IoHandle::Stderr => Some(libc::STDERR_FILENO),
+            IoHandle::File(file) => Some(file.borrow().get_ref().as_raw_fd()),
+            IoHandle::Buffer(_) | IoHandle::Null => None,
+        }
+    }
+
+    /// Reads a single byte, or `None` at end of input.
+    pub fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        match self.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// Reads up to and including the next newline, appending it to `line`.
+    /// Returns the number of bytes read, 0 at end of input.
+    pub fn read_line(&mut self, line: &mut String) -> io::Result<usize> {
+        let mut bytes = vec![];
+        while let Some(b) = self.read_byte()? {
+            bytes.push(b);
+            if b == b'\n' {
+                break;
+            }
+        }
+        line.push_str(&String::from_utf8_lossy(&bytes));
+        Ok(bytes.len())
+    }
+}
+
+/// Writes are buffered until flushed, which `CommandRegistry::run_builtin`
+/// does when a builtin finishes, so write errors surface there. Standard
+/// output also flushes each full line. Writes to a closed pipe fail with
+/// `BrokenPipe` rather than killing the shell, since the Rust runtime
+/// ignores `SIGPIPE`; forked children restore it.
+impl Write for IoHandle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            IoHandle::Stdout => io::stdout().write(buf),
+            IoHandle::Stderr => io::stderr().write(buf),
+            IoHandle::File(file) => file.borrow_mut().write(buf),
+            IoHandle::Buffer(out) => {
+                out.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            IoHandle::Null => Ok(buf.len()),
+            IoHandle::Stdin => Err(io::Error::from_raw_os_error(libc::EBADF)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
         match self {
-            IoHandle::Stdout => writeln!(io::stdout(), "{}", msg),
-            IoHandle::Stderr => writeln!(io::stderr(), "{}", msg),
-            IoHandle::File(file) => {
-                let mut _file = file.borrow_mut();
-                writeln!(_file, "{}", msg)
+            IoHandle::Stdout => io::stdout().flush(),
+            IoHandle::Stderr => io::stderr().flush(),
+            IoHandle::File(file) => file.borrow_mut().flush(),
+            IoHandle::Buffer(_) | IoHandle::Null | IoHandle::Stdin => Ok(()),
+        }
+    }
+}
+
+/// Reads are unbuffered so that whatever a builtin leaves unread is still
+/// there for the next reader, such as a command started after `read`.
+impl Read for IoHandle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(fd) = self.raw_fd() else {
+            return Ok(0);
+        };
+        loop {
+            let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+            if n >= 0 {
+                return Ok(n as usize);
             }
-            IoHandle::Null => Ok(()),
-            _ => unreachable!("Cannot write to stdin stream"),
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Output buffers stood in for by unlinked temporary files while a child
+/// process runs, since a child cannot write into the shell's memory. See
+/// `CommandContext::spill`.
+#[must_use]
+pub struct Spill {
+    stdout: Option<Spilled>,
+    stderr: Option<Spilled>,
+}
+
+/// A buffer and the file standing in for it.
+type Spilled = (Rc<RefCell<Vec<u8>>>, Rc<RefCell<BufWriter<File>>>);
+
+impl Spill {
+    /// Copies what children wrote back into the buffers and puts the
+    /// buffers back in place.
+    pub fn collect(self, ctx: &mut CommandContext) {
+        if let Some((buf, file)) = self.stdout {
+            drain_into(&buf, &file);
+            ctx.stdout = IoHandle::Buffer(buf);
+        }
+        if let Some((buf, file)) = self.stderr {
+            drain_into(&buf, &file);
+            ctx.stderr = IoHandle::Buffer(buf);
         }
     }
 }
 
+fn spill_file(handle: &mut IoHandle) -> Option<Spilled> {
+    let IoHandle::Buffer(buf) = handle else {
+        return None;
+    };
+    let buf = buf.clone();
+    *handle = IoHandle::file(temp_file().ok()?);
+    let IoHandle::File(file) = handle else {
+        return None;
+    };
+    Some((buf, file.clone()))
+}
+
+fn drain_into(buf: &Rc<RefCell<Vec<u8>>>, file: &Rc<RefCell<BufWriter<File>>>) {
+    let mut file = file.borrow_mut();
+    file.flush().ok();
+    let file = file.get_mut();
+    if file.seek(SeekFrom::Start(0)).is_ok() {
+        file.read_to_end(&mut buf.borrow_mut()).ok();
+    }
+}
+
+/// Opens a new temporary file that is unlinked straight away, so it goes
+/// when the last handle to it closes.
+fn temp_file() -> io::Result<File> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = env::temp_dir().join(format!("shell-{}-{}", std::process::id(), n));
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    fs::remove_file(&path)?;
+    Ok(file)
+}
+
+/// Per-shell execution state. Cloning it takes a snapshot; subshells get
+/// their own copy in the forked child so changes never leak back.
+#[derive(Clone)]
 pub struct CommandContext {
     pub stdin: IoHandle,
     pub stdout: IoHandle,
     pub stderr: IoHandle,
-    pub env: HashMap<String, String>,
+    pub vars: Variables,
+    pub params: Params,
+    pub last_status: i32,
+    pub jobs: JobTable,
+    pub options: ShellOptions,
+    pub interactive: bool,
+    /// Nesting depth of contexts where `set -e` does not apply, such as the
+    /// left side of `&&`/`||` and negated pipelines.
+    pub errexit_ignored: usize,
+    pub hash: HashTable,
+    pub dir_stack: DirStack,
+    pub traps: TrapTable,
+}
+
+impl CommandContext {
+    /// Looks up a shell variable. Positional and special parameters are
+    /// resolved here as well; `$@` and `$*` as they expand in a context
+    /// that makes a single field.
+    pub fn get_var(&self, name: &str) -> Option<String> {
+        let params = &self.params;
+        match name {
+            "?" => Some(self.last_status.to_string()),
+            "$" => Some(params.shell_pid.to_string()),
+            "!" => params.last_background.map(|pid| pid.to_string()),
+            "_" => Some(params.last_arg.clone()),
+            "#" => Some(params.positional.len().to_string()),
+            "@" => Some(params.positional.join(" ")),
+            // joined with the first character of IFS
+            "*" => {
+                let sep = match self.vars.get("IFS") {
+                    Some(ifs) => ifs.chars().next().map(String::from).unwrap_or_default(),
+                    None => " ".into(),
+                };
+                Some(params.positional.join(&sep))
+            }
+            "0" => Some(params.arg0.clone()),
+            _ if name.starts_with(|c: char| c.is_ascii_digit()) => {
+                params.nth(name.parse().ok()?).map(str::to_string)
+            }
+            "-" => {
+                let mut flags = self.options.flags();
+                if self.interactive {
+                    flags.insert(0, 'i');
+                }
+                Some(flags)
+            }
+            _ => self.vars.get(name).map(str::to_string),
+        }
+    }
+
+    /// Swaps in-memory standard output and error for temporary files before
+    /// starting a child process. `Spill::collect` undoes it once the child
+    /// is done.
+    pub fn spill(&mut self) -> Spill {
+        Spill {
+            stdout: spill_file(&mut self.stdout),
+            stderr: spill_file(&mut self.stderr),
+        }
+    }
+
+    /// Writes a builtin's output, so that a failure such as a closed pipe
+    /// is reported as `name: write error: ...`. The output is buffered
+    /// until the builtin finishes.
+    pub fn write_stdout(&mut self, bytes: &[u8]) -> Result<(), ShellError> {
+        self.stdout.write_all(bytes).map_err(ShellError::Write)
+    }
+
+    /// Writes one line of a builtin's output with [`Self::write_stdout`].
+    pub fn write_stdout_line(&mut self, line: &str) -> Result<(), ShellError> {
+        self.write_stdout(format!("{}\n", line).as_bytes())
+    }
+
+    /// The current value of `PATH`, for command lookup.
+    pub fn path_var(&self) -> String {
+        self.get_var("PATH").unwrap_or_default()
+    }
+}
+
+/// A command argument once expanded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub text: String,
+    /// Whether it was written as `name=(...)` after a declaration builtin,
+    /// such as `declare`. Only these assign arrays; an expanded value that
+    /// looks like one does not.
+    pub array: bool,
+}
+
+impl From<&str> for Argument {
+    fn from(text: &str) -> Self {
+        Argument {
+            text: text.to_string(),
+            array: false,
+        }
+    }
+}
+
+impl From<String> for Argument {
+    fn from(text: String) -> Self {
+        Argument { text, array: false }
+    }
 }
 
 pub trait Command {
     fn name(&self) -> &'static str;
+    /// One line saying what the command does, for `help` listings.
     fn description(&self) -> &'static str;
-    fn execute(&self, args: &[&str], ctx: &mut CommandContext);
+    /// The synopsis, such as `cd [dir]`.
+    fn usage(&self) -> &'static str {
+        self.name()
+    }
+    /// The detailed text shown by `help name` and `name --help`.
+    fn help(&self) -> &'static str {
+        ""
+    }
+    /// Runs the command, returning its exit status. A `ShellError::Failed`
+    /// is reported as `name: message`; a `ShellError::Control` passes up to
+    /// whatever handles it.
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError>;
+    /// Runs the command with its arguments as expanded. Only commands that
+    /// take array assignments, or pass them on, need more than the text.
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let args: Vec<&str> = args.iter().map(|a| a.text.as_str()).collect();
+        self.execute(&args, ctx)
+    }
     fn is_builtin(&self) -> bool {
         true
     }
+    /// Whether a lone `--help` argument shows the help text. Commands that
+    /// treat `--help` as an operand, such as `echo` and `test`, opt out.
+    fn accepts_help_flag(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,60 @@
+use crate::cli::command::{Argument, Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::registry::CommandRegistry;
+use std::cell::RefCell;
+use std::rc::Weak;
+
+/// `builtin name [args]` runs the builtin `name` even if something else
+/// would be found first.
+pub struct BuiltinCommand {
+    pub registry: Weak<RefCell<CommandRegistry>>,
+}
+
+impl Command for BuiltinCommand {
+    fn name(&self) -> &'static str {
+        "builtin"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a shell builtin"
+    }
+
+    fn usage(&self) -> &'static str {
+        "builtin [shell-builtin [arg ...]]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Runs the shell builtin SHELL-BUILTIN with ARGS, even when another
+command of the same name would be found first.
+
+Exit Status:
+Returns the exit status of SHELL-BUILTIN, or 1 if it is not a builtin."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let args: Vec<Argument> = args.iter().map(|&a| a.into()).collect();
+        self.execute_args(&args, ctx)
+    }
+
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let Some(name) = args.first().map(|a| a.text.as_str()) else {
+            return Ok(0);
+        };
+        let Some(reg) = self.registry.upgrade() else {
+            return Ok(0);
+        };
+        let reg = reg.borrow();
+        match reg.get(name).filter(|cmd| cmd.is_builtin()) {
+            Some(cmd) => CommandRegistry::run_builtin(cmd, args, ctx),
+            None => Err(ShellError::failed(
+                1,
+                format!("{}: not a shell builtin", name),
+            )),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::cli::command::{Command, CommandContext};
-use crate::utils::path_lookup::expand_tilde;
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::utils::{paths, sys};
 use std::env;
-use std::io::ErrorKind;
+use std::path::Path;
 
 pub struct CdCommand;
 
@@ -14,32 +15,137 @@ impl Command for CdCommand {
         "Change the shell working directory"
     }
 
-    fn execute(&self, args: &[&str], ctx: &mut CommandContext) {
-        let raw_target = if args.is_empty() {
-            ctx.env.get("HOME").map(|s| s.as_str()).unwrap_or("/")
-        } else {
-            args[0]
+    fn usage(&self) -> &'static str {
+        "cd [-L|-P] [dir | -]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Changes the current directory to DIR. The default DIR is the value of
+the HOME shell variable, and `cd -' returns to OLDPWD.
+
+A relative DIR is searched for in the colon-separated directories of
+CDPATH. When the directory is found that way, or with `cd -', the new
+working directory is printed.
+
+Options:
+  -L  follow symbolic links, resolving `..' against PWD (the default)
+  -P  use the physical directory structure, resolving symbolic links
+      (the default under `set -o physical')
+
+Exit Status:
+Returns 0 if the directory is changed, non-zero otherwise."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut physical = ctx.options.physical;
+        let mut args = args;
+        while let Some(flags) = args.first().and_then(|a| a.strip_prefix('-')) {
+            if flags.is_empty() {
+                break; // `cd -`
+            }
+            args = &args[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'L' => physical = false,
+                    'P' => physical = true,
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+        if args.len() > 1 {
+            return Err(ShellError::failed(1, "too many arguments"));
+        }
+        let (target, print) = match args.first() {
+            Some(&"-") => match ctx.get_var("OLDPWD") {
+                Some(dir) => (dir, true),
+                None => return Err(ShellError::failed(1, "OLDPWD not set")),
+            },
+            Some(dir) => match search_cdpath(dir, ctx) {
+                Some(found) => (found, true),
+                None => (dir.to_string(), false),
+            },
+            None => match ctx.get_var("HOME") {
+                Some(dir) => (dir, false),
+                None => return Err(ShellError::failed(1, "HOME not set")),
+            },
         };
 
-        let target = expand_tilde(raw_target);
-
-        if let Err(e) = env::set_current_dir(&target) {
-            match e.kind() {
-                ErrorKind::NotFound => ctx
-                    .stderr
-                    .write_line(format!("cd: {}: No such file or directory", target).as_str())
-                    .ok(),
-                _ => ctx
-                    .stderr
-                    .write_line(format!("cd: {}: {}", target, e).as_str())
-                    .ok(),
-            };
-            return;
+        change_dir(&target, physical, ctx).map_err(|e| ShellError::failed(1, e))?;
+        if print {
+            let pwd = ctx.get_var("PWD").unwrap_or_default() + "\n";
+            ctx.write_stdout(pwd.as_bytes())?;
         }
+        Ok(0)
+    }
+}
 
-        // Update ctx's pwd
-        if let Ok(new_dir) = env::current_dir() {
-            ctx.env.insert("PWD".into(), new_dir.display().to_string());
+/// Changes the working directory to `target`, moving the old `PWD` to
+/// `OLDPWD`. The error names `target`.
+///
+/// Unless `physical` is set, `..` in `target` is resolved lexically
+/// against `PWD`, so leaving a symlinked directory returns to where it was
+/// entered from; `PWD` then keeps the symlink rather than the resolved path.
+pub fn change_dir(target: &str, physical: bool, ctx: &mut CommandContext) -> Result<(), String> {
+    let old = ctx.get_var("PWD").filter(|pwd| paths::is_valid_pwd(pwd));
+    let logical = match &old {
+        Some(pwd) if !physical => Some(paths::logical_join(pwd, target)),
+        _ => None,
+    };
+    // A logical path that does not exist (say `link/..` where `link` is
+    // gone) falls back to a physical change.
+    let logical = logical.filter(|dir| env::set_current_dir(dir).is_ok());
+    if logical.is_none() {
+        env::set_current_dir(target)
+            .map_err(|e| format!("{}: {}", target, sys::error_message(&e)))?;
+    }
+
+    if let Some(old) = old.or_else(|| ctx.get_var("PWD")) {
+        ctx.vars.set("OLDPWD", old).map_err(|e| e.to_string())?;
+    }
+    let new_dir = match logical {
+        Some(dir) => dir,
+        None => physical_dir().unwrap_or_else(|| target.to_string()),
+    };
+    ctx.vars.set("PWD", new_dir).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The current directory with symbolic links resolved.
+pub fn physical_dir() -> Option<String> {
+    env::current_dir().ok().map(|d| d.display().to_string())
+}
+
+/// Finds a relative `dir` under one of the `CDPATH` directories. Only a
+/// match through a non-empty entry is returned, since that is when `cd`
+/// prints where it went.
+fn search_cdpath(dir: &str, ctx: &CommandContext) -> Option<String> {
+    if dir.starts_with('/') || dir == "." || dir == ".." {
+        return None;
+    }
+    if dir.starts_with("./") || dir.starts_with("../") {
+        return None;
+    }
+    let cdpath = ctx.get_var("CDPATH")?;
+    for base in cdpath.split(':') {
+        if base.is_empty() || base == "." {
+            if Path::new(dir).is_dir() {
+                return None;
+            }
+            continue;
+        }
+        let cand = Path::new(base).join(dir);
+        if cand.is_dir() {
+            return Some(cand.display().to_string());
         }
     }
+    None
 }
@@ -0,0 +1,129 @@
+use crate::cli::command::{Argument, Command, CommandContext};
+use crate::cli::commands::type_cmd::{resolve, Resolution};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::registry::CommandRegistry;
+use crate::utils::path_lookup::find_in_path;
+use std::cell::RefCell;
+use std::rc::Weak;
+
+/// The `PATH` used by `command -p`, which finds the standard utilities
+/// whatever the user's `PATH` is.
+const STANDARD_PATH: &str = "/bin:/usr/bin";
+
+/// `command name [args]` runs a builtin or program, skipping shell
+/// functions and aliases. `-v` and `-V` describe `name` instead.
+pub struct CommandCommand {
+    pub registry: Weak<RefCell<CommandRegistry>>,
+}
+
+impl Command for CommandCommand {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a command, bypassing shell functions"
+    }
+
+    fn usage(&self) -> &'static str {
+        "command [-pVv] command [arg ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Runs COMMAND with ARGS, ignoring shell functions, or describes it.
+
+Options:
+  -p  search a default PATH that finds the standard utilities
+  -v  print the word or path the shell would run for COMMAND
+  -V  print a more verbose description of COMMAND
+
+Exit Status:
+Returns the exit status of COMMAND, or 1 if it is not found."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let args: Vec<Argument> = args.iter().map(|&a| a.into()).collect();
+        self.execute_args(&args, ctx)
+    }
+
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let (mut standard, mut short, mut verbose) = (false, false, false);
+        let mut rest = args;
+        while let Some(flags) = rest.first().and_then(|a| a.text.strip_prefix('-')) {
+            rest = &rest[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'p' => standard = true,
+                    'v' => short = true,
+                    'V' => verbose = true,
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+        let Some(name) = rest.first().map(|a| a.text.as_str()) else {
+            return Ok(0);
+        };
+        let Some(reg) = self.registry.upgrade() else {
+            return Ok(0);
+        };
+        let reg = reg.borrow();
+
+        if short || verbose {
+            let mut status = 0;
+            for name in rest.iter().map(|a| a.text.as_str()) {
+                match resolve(name, &reg, ctx, false, false).first() {
+                    Some(Resolution::File(path)) if short => {
+                        ctx.write_stdout_line(&path.display().to_string())?;
+                    }
+                    Some(_) if short => {
+                        ctx.write_stdout_line(name)?;
+                    }
+                    Some(res) => {
+                        ctx.write_stdout_line(&res.describe(name))?;
+                    }
+                    None => {
+                        if verbose {
+                            ctx.stderr
+                                .write_line(format!("command: {}: not found", name).as_str())
+                                .ok();
+                        }
+                        status = 1;
+                    }
+                }
+            }
+            return Ok(status);
+        }
+
+        if let Some(cmd) = reg.get(name) {
+            return CommandRegistry::run_builtin(cmd, rest, ctx);
+        }
+        let argv: Vec<String> = rest.iter().map(|a| a.text.clone()).collect();
+        let path = if standard {
+            find_in_path(name, STANDARD_PATH)
+        } else {
+            ctx.hash.lookup(name, &ctx.path_var())
+        };
+        match path {
+            Some(path) => CommandRegistry::run_external(&path, &argv, ctx),
+            None => {
+                ctx.stderr
+                    .write_line(format!("{}: not found", name).as_str())
+                    .ok();
+                Ok(127)
+            }
+        }
+    }
+}
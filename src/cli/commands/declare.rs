@@ -0,0 +1,406 @@
+use crate::cli::command::{Argument, Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::parser::parse_simple::parse_array;
+use crate::cli::parser::word::{Word, WordPart};
+use crate::cli::vars::{is_name, Value, Variable};
+
+pub struct DeclareCommand;
+pub struct TypesetCommand;
+pub struct ReadonlyCommand;
+pub struct LocalCommand;
+
+const HELP: &str = "\
+Declares each NAME, assigning VALUE if given, and sets its attributes.
+A VALUE of the form (ITEM ...) assigns an array, in which each ITEM may
+start with [SUBSCRIPT]=. With no NAMEs, prints the variables that have
+all the attributes given as commands that recreate them.
+
+Options:
+  -a  make each NAME an indexed array
+  -A  make each NAME an associative array
+  -f  list functions; there are none
+  -F  list function names; there are none
+  -i  evaluate assignments as integers
+  -l  convert assigned values to lower case
+  -n  make each NAME refer to the variable named by its value
+  -p  print each NAME, or every variable
+  -r  make each NAME readonly
+  -u  convert assigned values to upper case
+  -x  export each NAME
+
+Using `+' instead of `-' turns an attribute off, except for -r and
+arrays.
+
+Exit Status:
+Returns 0 unless an invalid option is given or a NAME is not valid, is
+readonly or cannot take the attributes asked for.";
+
+/// A `name[subscript]+=value` argument split into its pieces.
+pub struct Declaration<'a> {
+    pub name: &'a str,
+    pub subscript: Option<&'a str>,
+    pub append: bool,
+    pub value: Option<&'a str>,
+    /// Whether the value was written as `(...)` and assigns an array.
+    pub array: bool,
+}
+
+impl<'a> Declaration<'a> {
+    /// Splits the text of `arg`.
+    pub fn parse(arg: &'a Argument) -> Self {
+        let (target, append, value) = match arg.text.split_once('=') {
+            Some((t, v)) => match t.strip_suffix('+') {
+                Some(t) => (t, true, Some(v)),
+                None => (t, false, Some(v)),
+            },
+            None => (arg.text.as_str(), false, None),
+        };
+        let (name, subscript) = match target.strip_suffix(']').and_then(|t| t.split_once('[')) {
+            Some((name, sub)) => (name, Some(sub)),
+            None => (target, None),
+        };
+        Declaration {
+            name,
+            subscript,
+            append,
+            value,
+            array: arg.array,
+        }
+    }
+
+    /// Assigns the value, which is an array if written as `(...)`.
+    pub fn assign(&self, value: &str, ctx: &mut CommandContext) -> Result<(), ShellError> {
+        let items = value
+            .strip_prefix('(')
+            .and_then(|v| v.strip_suffix(')'))
+            .filter(|_| self.array);
+        match items {
+            Some(items) => {
+                let items = parse_array(items)
+                    .map_err(|e| ShellError::failed(1, format!("{}: {}", self.name, e)))?
+                    .iter()
+                    .map(|item| (item.subscript.as_ref().map(text_of), text_of(&item.value)))
+                    .collect();
+                ctx.vars.assign_array(self.name, items, self.append)
+            }
+            None => ctx
+                .vars
+                .assign(self.name, self.subscript, value.to_string(), self.append),
+        }
+    }
+}
+
+/// The text of an array item as the shell passes it on: quoted, with
+/// nothing left to expand.
+fn text_of(word: &Word) -> String {
+    word.parts
+        .iter()
+        .map(|part| match part {
+            WordPart::Literal(s) | WordPart::Quoted(s) => s.as_str(),
+            _ => "",
+        })
+        .collect()
+}
+
+/// The attributes to turn on and off, by option letter.
+#[derive(Default)]
+struct Attributes {
+    on: String,
+    off: String,
+}
+
+impl Attributes {
+    /// Whether `var` has every attribute being turned on.
+    fn matches(&self, var: &Variable) -> bool {
+        self.on.chars().all(|flag| match flag {
+            'a' => matches!(var.value, Some(Value::Indexed(_))),
+            'A' => matches!(var.value, Some(Value::Assoc(_))),
+            'i' => var.integer,
+            'l' => var.lowercase,
+            'n' => var.nameref,
+            'r' => var.readonly,
+            'u' => var.uppercase,
+            _ => var.exported,
+        })
+    }
+
+    /// Declares `decl.name` with these attributes. Readonly comes last, so
+    /// that the value can still be assigned.
+    fn apply(&self, decl: &Declaration, ctx: &mut CommandContext) -> Result<(), ShellError> {
+        let name = decl.name;
+        if let Some(var) = ctx.vars.lookup(name) {
+            let only_readonly = self.on.chars().all(|c| c == 'r') && self.off.is_empty();
+            if var.readonly && (decl.value.is_some() || !only_readonly) {
+                return Err(ShellError::failed(
+                    1,
+                    format!("{}: readonly variable", name),
+                ));
+            }
+            let array = matches!(var.value, Some(Value::Indexed(_) | Value::Assoc(_)));
+            if array && self.off.contains(['a', 'A']) {
+                let msg = format!("{}: cannot destroy array variables in this way", name);
+                return Err(ShellError::failed(1, msg));
+            }
+        }
+        for flag in self.off.chars() {
+            match flag {
+                'i' => ctx.vars.set_integer(name, false),
+                'l' => ctx.vars.set_lowercase(name, false),
+                'n' => ctx.vars.clear_nameref(name),
+                'u' => ctx.vars.set_uppercase(name, false),
+                'x' => ctx.vars.export(name, false),
+                _ => {}
+            }
+        }
+        for flag in self.on.chars() {
+            match flag {
+                'a' => ctx.vars.declare_array(name, false)?,
+                'A' => ctx.vars.declare_array(name, true)?,
+                'i' => ctx.vars.set_integer(name, true),
+                'l' => ctx.vars.set_lowercase(name, true),
+                'u' => ctx.vars.set_uppercase(name, true),
+                'x' => ctx.vars.export(name, true),
+                _ => {}
+            }
+        }
+        match decl.value {
+            // the value of a nameref is the name it refers to
+            _ if self.on.contains('n') => ctx.vars.set_nameref(name, decl.value)?,
+            Some(value) => decl.assign(value, ctx)?,
+            None => {}
+        }
+        if self.on.contains('r') {
+            ctx.vars.set_readonly(name);
+        }
+        Ok(())
+    }
+}
+
+/// Runs `declare` and the commands that are forms of it. `readonly`
+/// starts with the readonly attribute and accepts fewer options.
+fn declare(
+    cmd: &dyn Command,
+    args: &[Argument],
+    options: &str,
+    mut attrs: Attributes,
+    ctx: &mut CommandContext,
+) -> Result<ExitStatus, ShellError> {
+    let mut print = false;
+    let mut functions = false;
+    let mut names = args;
+    while let Some(arg) = names
+        .first()
+        .map(|a| a.text.as_str())
+        .filter(|a| a.len() > 1)
+    {
+        let (on, flags) = match (arg.strip_prefix('-'), arg.strip_prefix('+')) {
+            (Some(flags), _) => (true, flags),
+            (None, Some(flags)) => (false, flags),
+            _ => break,
+        };
+        names = &names[1..];
+        if flags == "-" && on {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                _ if !options.contains(flag) => {
+                    let sign = if on { '-' } else { '+' };
+                    let msg = format!("{}{}: invalid option", sign, flag);
+                    return Err(ShellError::usage(cmd, msg));
+                }
+                'p' => print = true,
+                'f' | 'F' => functions = true,
+                _ if on => attrs.on.push(flag),
+                _ => attrs.off.push(flag),
+            }
+        }
+    }
+
+    // There are no functions, so none are listed and none are found.
+    if functions {
+        return Ok(if names.is_empty() { 0 } else { 1 });
+    }
+    if names.is_empty() {
+        let lines: Vec<String> = ctx
+            .vars
+            .iter()
+            .filter(|(_, var)| attrs.matches(var))
+            .map(|(name, var)| var.declaration(name))
+            .collect();
+        for line in lines {
+            ctx.write_stdout_line(&line)?;
+        }
+        return Ok(0);
+    }
+
+    let mut status = 0;
+    for arg in names {
+        let decl = Declaration::parse(arg);
+        if print {
+            match ctx.vars.lookup(decl.name) {
+                Some(var) => {
+                    let line = var.declaration(decl.name);
+                    ctx.write_stdout_line(&line)?;
+                }
+                None => {
+                    let msg = format!("{}: {}: not found", cmd.name(), arg.text);
+                    ctx.stderr.write_line(&msg).ok();
+                    status = 1;
+                }
+            }
+            continue;
+        }
+        if !is_name(decl.name) {
+            let msg = format!("{}: `{}': not a valid identifier", cmd.name(), arg.text);
+            ctx.stderr.write_line(&msg).ok();
+            status = 1;
+            continue;
+        }
+        if let Err(e) = attrs.apply(&decl, ctx) {
+            ctx.stderr
+                .write_line(&format!("{}: {}", cmd.name(), e))
+                .ok();
+            status = 1;
+        }
+    }
+    Ok(status)
+}
+
+impl Command for DeclareCommand {
+    fn name(&self) -> &'static str {
+        "declare"
+    }
+
+    fn description(&self) -> &'static str {
+        "Set variable values and attributes"
+    }
+
+    fn usage(&self) -> &'static str {
+        "declare [-aAfFilnprux] [name[=value] ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let args: Vec<Argument> = args.iter().map(|&a| a.into()).collect();
+        self.execute_args(&args, ctx)
+    }
+
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        declare(self, args, "aAfFilnprux", Attributes::default(), ctx)
+    }
+}
+
+impl Command for TypesetCommand {
+    fn name(&self) -> &'static str {
+        "typeset"
+    }
+
+    fn description(&self) -> &'static str {
+        "Set variable values and attributes; the same as `declare'"
+    }
+
+    fn usage(&self) -> &'static str {
+        "typeset [-aAfFilnprux] [name[=value] ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let args: Vec<Argument> = args.iter().map(|&a| a.into()).collect();
+        self.execute_args(&args, ctx)
+    }
+
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        declare(self, args, "aAfFilnprux", Attributes::default(), ctx)
+    }
+}
+
+impl Command for ReadonlyCommand {
+    fn name(&self) -> &'static str {
+        "readonly"
+    }
+
+    fn description(&self) -> &'static str {
+        "Mark shell variables as unchangeable"
+    }
+
+    fn usage(&self) -> &'static str {
+        "readonly [-aAp] [name[=value] ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Marks each NAME readonly, assigning VALUE first if given. Readonly
+variables cannot be assigned, unset or have their attributes changed.
+With no NAMEs, or with -p, prints the readonly variables as commands
+that recreate them.
+
+Options:
+  -a  make each NAME an indexed array
+  -A  make each NAME an associative array
+  -p  list the readonly variables
+
+Exit Status:
+Returns 0 unless an invalid option is given or a NAME is not valid or is
+already readonly and given a VALUE."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let args: Vec<Argument> = args.iter().map(|&a| a.into()).collect();
+        self.execute_args(&args, ctx)
+    }
+
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let attrs = Attributes {
+            on: "r".into(),
+            off: String::new(),
+        };
+        declare(self, args, "aAp", attrs, ctx)
+    }
+}
+
+impl Command for LocalCommand {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn description(&self) -> &'static str {
+        "Define local variables"
+    }
+
+    fn usage(&self) -> &'static str {
+        "local [option] name[=value] ..."
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Declares each NAME local to the function being run, taking the same
+options as `declare'. The shell has no functions yet, so this always
+fails.
+
+Exit Status:
+Returns 1, since no function is being run."
+    }
+
+    fn execute(&self, _args: &[&str], _ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        Err(ShellError::failed(1, "can only be used in a function"))
+    }
+}
@@ -0,0 +1,244 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::commands::cd::change_dir;
+use crate::cli::error::{ExitStatus, ShellError};
+
+/// The directory stack with the current directory first.
+fn stack(ctx: &CommandContext) -> Vec<String> {
+    let pwd = ctx.get_var("PWD").unwrap_or_default();
+    ctx.dir_stack.entries(&pwd)
+}
+
+/// Makes `entries` the directory stack, changing to its first entry.
+fn set_stack(entries: Vec<String>, ctx: &mut CommandContext) -> Result<(), String> {
+    if entries[0] != ctx.get_var("PWD").unwrap_or_default() {
+        change_dir(&entries[0], ctx.options.physical, ctx)?;
+    }
+    ctx.dir_stack.set_entries(entries);
+    Ok(())
+}
+
+/// `dir` with a leading `$HOME` replaced by `~`, unless `long` is set.
+fn abbreviate(dir: &str, ctx: &CommandContext, long: bool) -> String {
+    match ctx.get_var("HOME").filter(|h| !long && !h.is_empty()) {
+        Some(home) if dir == home => "~".into(),
+        Some(home) => match dir.strip_prefix(&home).filter(|r| r.starts_with('/')) {
+            Some(rest) => format!("~{}", rest),
+            None => dir.into(),
+        },
+        None => dir.into(),
+    }
+}
+
+fn print_stack(ctx: &mut CommandContext) -> Result<(), ShellError> {
+    let line: Vec<String> = stack(ctx)
+        .iter()
+        .map(|d| abbreviate(d, ctx, false))
+        .collect();
+    ctx.write_stdout_line(&line.join(" "))
+}
+
+pub struct PushdCommand;
+
+impl Command for PushdCommand {
+    fn name(&self) -> &'static str {
+        "pushd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Add a directory to the directory stack"
+    }
+
+    fn usage(&self) -> &'static str {
+        "pushd [dir | +N | -N]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Adds a directory to the top of the directory stack, or rotates the
+stack, making the new top of the stack the current working directory.
+With no arguments, exchanges the top two directories.
+
+Arguments:
+  +N  rotate the stack so the Nth directory, counting from the left of
+      the list shown by `dirs' starting with zero, is at the top
+  -N  rotate the stack so the Nth directory, counting from the right,
+      is at the top
+  dir add DIR to the top of the stack and make it the current directory
+
+Exit Status:
+Returns 0 unless an invalid argument is given or the directory change
+fails."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut entries = stack(ctx);
+        let result = match args {
+            [] => {
+                if entries.len() < 2 {
+                    return Err(ShellError::failed(1, "no other directory"));
+                }
+                entries.swap(0, 1);
+                set_stack(entries, ctx)
+            }
+            [spec] if spec.len() > 1 && (spec.starts_with('+') || spec.starts_with('-')) => {
+                let Some(n) = ctx.dir_stack.index(spec) else {
+                    let msg = format!("{}: directory stack index out of range", spec);
+                    return Err(ShellError::failed(1, msg));
+                };
+                entries.rotate_left(n);
+                set_stack(entries, ctx)
+            }
+            [dir] => {
+                let old = entries[0].clone();
+                change_dir(dir, ctx.options.physical, ctx).map(|()| ctx.dir_stack.push(old))
+            }
+            _ => return Err(ShellError::failed(1, "too many arguments")),
+        };
+        result.map_err(|e| ShellError::failed(1, e))?;
+        print_stack(ctx)?;
+        Ok(0)
+    }
+}
+
+pub struct PopdCommand;
+
+impl Command for PopdCommand {
+    fn name(&self) -> &'static str {
+        "popd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Remove directories from the directory stack"
+    }
+
+    fn usage(&self) -> &'static str {
+        "popd [+N | -N]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Removes the top directory from the directory stack and changes to the
+new top directory. With an argument, removes that entry instead.
+
+Arguments:
+  +N  remove the Nth directory, counting from the left of the list
+      shown by `dirs' starting with zero
+  -N  remove the Nth directory, counting from the right
+
+Exit Status:
+Returns 0 unless an invalid argument is given or the directory change
+fails."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut entries = stack(ctx);
+        if entries.len() < 2 {
+            return Err(ShellError::failed(1, "directory stack empty"));
+        }
+        let n = match args {
+            [] => 0,
+            [spec] => match ctx.dir_stack.index(spec).filter(|_| spec.len() > 1) {
+                Some(n) if spec.starts_with('+') || spec.starts_with('-') => n,
+                _ => {
+                    let msg = format!("{}: invalid argument", spec);
+                    return Err(ShellError::failed(1, msg));
+                }
+            },
+            _ => return Err(ShellError::failed(1, "too many arguments")),
+        };
+        entries.remove(n);
+        set_stack(entries, ctx).map_err(|e| ShellError::failed(1, e))?;
+        print_stack(ctx)?;
+        Ok(0)
+    }
+}
+
+pub struct DirsCommand;
+
+impl Command for DirsCommand {
+    fn name(&self) -> &'static str {
+        "dirs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Display the directory stack"
+    }
+
+    fn usage(&self) -> &'static str {
+        "dirs [-clpv] [+N] [-N]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Displays the list of remembered directories, the current directory
+first. Directories are added with `pushd' and removed with `popd'.
+
+Options:
+  -c  clear the directory stack
+  -l  do not abbreviate the home directory as `~'
+  -p  print one directory per line
+  -v  print one directory per line with its position in the stack
+
+Arguments:
+  +N  show only the Nth entry counting from the left, starting with zero
+  -N  show only the Nth entry counting from the right
+
+Exit Status:
+Returns 0 unless an invalid option is given."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let (mut clear, mut long, mut per_line, mut numbered) = (false, false, false, false);
+        let mut only = None;
+        for arg in args {
+            if let Some(n) = ctx.dir_stack.index(arg).filter(|_| arg.len() > 1) {
+                only = Some(n);
+                continue;
+            }
+            let flags = match arg.strip_prefix('-') {
+                Some(f) if !f.is_empty() && !f.starts_with(|c: char| c.is_ascii_digit()) => f,
+                _ => {
+                    let msg = format!("{}: directory stack index out of range", arg);
+                    return Err(ShellError::failed(1, msg));
+                }
+            };
+            for flag in flags.chars() {
+                match flag {
+                    'c' => clear = true,
+                    'l' => long = true,
+                    'p' => per_line = true,
+                    'v' => numbered = true,
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+        if clear {
+            ctx.dir_stack.clear();
+            return Ok(0);
+        }
+
+        let entries: Vec<String> = stack(ctx)
+            .iter()
+            .map(|d| abbreviate(d, ctx, long))
+            .collect();
+        if let Some(n) = only {
+            ctx.write_stdout_line(&entries[n])?;
+        } else if numbered {
+            for (i, dir) in entries.iter().enumerate() {
+                ctx.write_stdout_line(&format!("{:>2}  {}", i, dir))?;
+            }
+        } else if per_line {
+            for dir in &entries {
+                ctx.write_stdout_line(dir)?;
+            }
+        } else {
+            ctx.write_stdout_line(&entries.join(" "))?;
+        }
+        Ok(0)
+    }
+}
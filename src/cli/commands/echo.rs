@@ -1,4 +1,6 @@
 use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::utils::escape;
 
 pub struct EchoCommand;
 
@@ -8,12 +10,60 @@ impl Command for EchoCommand {
     }
 
     fn description(&self) -> &'static str {
-        "Echos input arguments"
+        "Write arguments to standard output"
     }
 
-    fn execute(&self, args: &[&str], ctx: &mut CommandContext) {
-        ctx.stdout
-            .write_line(format!("{}", args.join(" ")).as_str())
-            .ok();
+    fn usage(&self) -> &'static str {
+        "echo [-neE] [arg ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Writes the arguments to standard output, separated by single spaces
+and followed by a newline.
+
+Options:
+  -n  do not append a newline
+  -e  interpret backslash escapes: \\a \\b \\c (stop output) \\e \\f \\n \\r
+      \\t \\v \\\\ \\0nnn (octal) \\xHH \\uHHHH \\UHHHHHHHH
+  -E  do not interpret backslash escapes (the default)"
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let (mut newline, mut escapes) = (true, false);
+        let mut words = args;
+        // only words made entirely of known flags are options
+        while let Some(flags) = words
+            .first()
+            .and_then(|w| w.strip_prefix('-'))
+            .filter(|f| !f.is_empty() && f.chars().all(|c| "neE".contains(c)))
+        {
+            for flag in flags.chars() {
+                match flag {
+                    'n' => newline = false,
+                    'e' => escapes = true,
+                    _ => escapes = false,
+                }
+            }
+            words = &words[1..];
+        }
+
+        let text = words.join(" ");
+        let mut out = if escapes {
+            let (bytes, stopped) = escape::unescape(&text, true);
+            newline &= !stopped;
+            bytes
+        } else {
+            text.into_bytes()
+        };
+        if newline {
+            out.push(b'\n');
+        }
+        ctx.write_stdout(&out)?;
+        Ok(0)
+    }
+
+    fn accepts_help_flag(&self) -> bool {
+        false
     }
 }
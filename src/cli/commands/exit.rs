@@ -1,4 +1,5 @@
 use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
 
 pub struct ExitCommand;
 
@@ -8,18 +9,37 @@ impl Command for ExitCommand {
     }
 
     fn description(&self) -> &'static str {
-        "exits shell with given exit status"
+        "Exit the shell"
     }
 
-    fn execute(&self, args: &[&str], _ctx: &mut CommandContext) {
-        let status_code = match args.get(0) {
-            Some(status_code_str) => status_code_str.parse::<i32>().expect("Invalid status code"),
-            None => {
-                // TODO: record last cmd status code
-                // and exit with the same instead of 0
-                0
+    fn usage(&self) -> &'static str {
+        "exit [n]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Exits the shell with a status of N. If N is omitted, the exit status
+is that of the last command executed. A non-numeric N is an error that
+still exits, with status 2, as does more than one N, with status 1."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let status = match args {
+            [] => ctx.last_status,
+            [n] => match n.trim().parse::<i64>() {
+                // Statuses wrap to a byte, so `exit -1` is 255.
+                Ok(n) => (n & 0xff) as ExitStatus,
+                Err(_) => {
+                    let msg = format!("exit: {}: numeric argument required", n);
+                    ctx.stderr.write_line(&msg).ok();
+                    2
+                }
+            },
+            _ => {
+                ctx.stderr.write_line("exit: too many arguments").ok();
+                1
             }
         };
-        std::process::exit(status_code);
+        Err(ShellError::exit(status))
     }
 }
@@ -0,0 +1,100 @@
+use crate::cli::command::{Argument, Command, CommandContext};
+use crate::cli::commands::declare::Declaration;
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::vars::is_name;
+
+pub struct ExportCommand;
+
+impl Command for ExportCommand {
+    fn name(&self) -> &'static str {
+        "export"
+    }
+
+    fn description(&self) -> &'static str {
+        "Set export attribute for shell variables"
+    }
+
+    fn usage(&self) -> &'static str {
+        "export [-n] [name[=value] ...] or export -p"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Marks each NAME to be passed in the environment of commands run from
+now on. A VALUE is assigned first if given. With no NAMEs, or with -p,
+prints the exported variables as commands that recreate them.
+
+Options:
+  -n  remove the export attribute from each NAME
+  -p  list the exported variables
+
+Exit Status:
+Returns 0 unless an invalid option is given or a NAME is not valid or is
+readonly."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let args: Vec<Argument> = args.iter().map(|&a| a.into()).collect();
+        self.execute_args(&args, ctx)
+    }
+
+    fn execute_args(
+        &self,
+        args: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let mut export = true;
+        let mut names = args;
+        while let Some(flags) = names.first().and_then(|a| a.text.strip_prefix('-')) {
+            names = &names[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'n' => export = false,
+                    'p' => {}
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+
+        if names.is_empty() {
+            let lines: Vec<String> = ctx
+                .vars
+                .iter()
+                .filter(|(_, var)| var.exported)
+                .map(|(name, var)| var.declaration(name))
+                .collect();
+            for line in lines {
+                ctx.write_stdout_line(&line)?;
+            }
+            return Ok(0);
+        }
+
+        let mut status = 0;
+        for arg in names {
+            let decl = Declaration::parse(arg);
+            if !is_name(decl.name) {
+                let msg = format!("export: `{}': not a valid identifier", arg.text);
+                ctx.stderr.write_line(&msg).ok();
+                status = 1;
+                continue;
+            }
+            if let Some(value) = decl.value {
+                if let Err(e) = decl.assign(value, ctx) {
+                    ctx.stderr.write_line(&format!("export: {}", e)).ok();
+                    status = 1;
+                    continue;
+                }
+            }
+            ctx.vars.export(decl.name, export);
+        }
+        Ok(status)
+    }
+}
@@ -0,0 +1,133 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use std::path::Path;
+
+pub struct HashCommand;
+
+impl HashCommand {
+    fn list(ctx: &mut CommandContext) -> Result<(), ShellError> {
+        let lines: Vec<String> = ctx
+            .hash
+            .entries()
+            .map(|(_, entry)| format!("{:>4}\t{}", entry.hits, entry.path.display()))
+            .collect();
+        if lines.is_empty() {
+            return ctx.write_stdout_line("hash: hash table empty");
+        }
+        ctx.write_stdout_line("hits\tcommand")?;
+        for line in lines {
+            ctx.write_stdout_line(&line)?;
+        }
+        Ok(())
+    }
+
+    fn not_found(ctx: &mut CommandContext, name: &str) -> ExitStatus {
+        ctx.stderr
+            .write_line(format!("hash: {}: not found", name).as_str())
+            .ok();
+        1
+    }
+}
+
+impl Command for HashCommand {
+    fn name(&self) -> &'static str {
+        "hash"
+    }
+
+    fn description(&self) -> &'static str {
+        "Remember or display program locations"
+    }
+
+    fn usage(&self) -> &'static str {
+        "hash [-r] [-p pathname] [-dt] [name ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Determines and remembers the full pathname of each command NAME. With
+no arguments, lists the remembered commands and how often each was run.
+
+Options:
+  -d  forget the remembered location of each NAME
+  -p pathname
+      use PATHNAME as the full pathname of NAME
+  -r  forget all remembered locations
+  -t  print the remembered location of each NAME
+
+Exit Status:
+Returns 0 unless a NAME is not found or an invalid option is given."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let (mut reset, mut delete, mut show) = (false, false, false);
+        let mut pathname = None;
+        let mut iter = args.iter();
+        let mut names = vec![];
+        while let Some(arg) = iter.next() {
+            if *arg == "--" {
+                names.extend(iter.by_ref());
+                break;
+            }
+            let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+                names.push(*arg);
+                names.extend(iter.by_ref());
+                break;
+            };
+            for flag in flags.chars() {
+                match flag {
+                    'r' => reset = true,
+                    'd' => delete = true,
+                    't' => show = true,
+                    'p' => match iter.next() {
+                        Some(path) => pathname = Some(*path),
+                        None => {
+                            return Err(ShellError::usage(self, "-p: option requires an argument"))
+                        }
+                    },
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+
+        if reset {
+            ctx.hash.clear();
+        }
+        if names.is_empty() {
+            if !reset && !delete && !show && pathname.is_none() {
+                Self::list(ctx)?;
+            }
+            return Ok(0);
+        }
+
+        let path_var = ctx.path_var();
+        let mut status = 0;
+        for name in &names {
+            if let Some(path) = pathname {
+                ctx.hash.insert(name, Path::new(path));
+            } else if delete {
+                if !ctx.hash.remove(name) {
+                    status = Self::not_found(ctx, name);
+                }
+            } else if show {
+                let Some(entry) = ctx.hash.get(name) else {
+                    status = Self::not_found(ctx, name);
+                    continue;
+                };
+                let line = if names.len() > 1 {
+                    format!("{}\t{}", name, entry.path.display())
+                } else {
+                    entry.path.display().to_string()
+                };
+                ctx.write_stdout_line(&line)?;
+            } else if ctx.hash.find(name, &path_var).is_none() {
+                status = Self::not_found(ctx, name);
+            }
+        }
+        Ok(status)
+    }
+}
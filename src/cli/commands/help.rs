@@ -0,0 +1,132 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::registry::{help_text, CommandRegistry};
+use crate::utils::glob;
+use std::cell::RefCell;
+use std::rc::Weak;
+
+/// The widest a synopsis gets in the listing; longer ones are cut short
+/// and marked with `>`, as bash does.
+const USAGE_WIDTH: usize = 36;
+
+/// Lists the builtins, or shows help for those matching each pattern.
+pub struct HelpCommand {
+    pub registry: Weak<RefCell<CommandRegistry>>,
+}
+
+impl Command for HelpCommand {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn description(&self) -> &'static str {
+        "Display information about builtin commands"
+    }
+
+    fn usage(&self) -> &'static str {
+        "help [-ds] [pattern ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Displays brief summaries of builtin commands. If PATTERN is given, shows
+detailed help on every command matching PATTERN, otherwise lists all
+builtins.
+
+Options:
+  -d  print a short description of each topic
+  -s  print only the usage synopsis of each topic
+
+Exit Status:
+Returns 0 unless no command matches a PATTERN or an invalid option is
+given."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let (mut short, mut synopsis) = (false, false);
+        let mut patterns = args;
+        while let Some(flags) = patterns.first().and_then(|a| a.strip_prefix('-')) {
+            patterns = &patterns[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'd' => short = true,
+                    's' => synopsis = true,
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+        let Some(reg) = self.registry.upgrade() else {
+            return Ok(0);
+        };
+        let reg = reg.borrow();
+        let builtins: Vec<_> = reg
+            .commands()
+            .into_iter()
+            .filter(|cmd| cmd.is_builtin())
+            .collect();
+
+        if patterns.is_empty() {
+            ctx.write_stdout_line(
+                "Shell builtins. Type `help name' to find out more about `name'.",
+            )?;
+            ctx.write_stdout_line("")?;
+            let width = builtins
+                .iter()
+                .map(|cmd| cmd.usage().len().min(USAGE_WIDTH))
+                .max();
+            for cmd in &builtins {
+                let line = format!(
+                    " {:<width$}  {}",
+                    truncate(cmd.usage()),
+                    cmd.description(),
+                    width = width.unwrap_or(0)
+                );
+                ctx.write_stdout_line(line.trim_end())?;
+            }
+            return Ok(0);
+        }
+
+        let mut status = 0;
+        for pattern in patterns {
+            let matching: Vec<_> = builtins
+                .iter()
+                .filter(|cmd| glob::pattern_matches(pattern, cmd.name()))
+                .collect();
+            if matching.is_empty() {
+                ctx.stderr
+                    .write_line(format!("help: no help topics match `{}'.", pattern).as_str())
+                    .ok();
+                status = 1;
+                continue;
+            }
+            for cmd in matching {
+                let text = if short {
+                    format!("{} - {}", cmd.name(), cmd.description())
+                } else if synopsis {
+                    format!("{}: {}", cmd.name(), cmd.usage())
+                } else {
+                    help_text(cmd.as_ref())
+                };
+                ctx.write_stdout_line(&text)?;
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// Cuts `usage` down to `USAGE_WIDTH` characters.
+fn truncate(usage: &str) -> String {
+    if usage.chars().count() <= USAGE_WIDTH {
+        return usage.to_string();
+    }
+    let kept: String = usage.chars().take(USAGE_WIDTH - 1).collect();
+    kept + ">"
+}
@@ -0,0 +1,136 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::utils::signal;
+use crate::utils::sys::error_message;
+use std::io;
+
+pub struct KillCommand;
+
+impl KillCommand {
+    /// Lists every signal, or translates each of `specs` between name and
+    /// number. A number above 128 is read as an exit status such as `$?`.
+    fn list(&self, specs: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        if specs.is_empty() {
+            for line in signal::listing() {
+                ctx.write_stdout_line(&line)?;
+            }
+            return Ok(0);
+        }
+        let mut status = 0;
+        for spec in specs {
+            let translated = match spec.parse::<libc::c_int>() {
+                Ok(n) if n > 128 => signal::name(n - 128).map(str::to_string),
+                Ok(n) => signal::name(n).map(str::to_string),
+                Err(_) => signal::number(spec)
+                    .filter(|&n| n != 0)
+                    .map(|n| n.to_string()),
+            };
+            match translated {
+                Some(line) => {
+                    ctx.write_stdout_line(&line)?;
+                }
+                None => {
+                    let msg = format!("kill: {}: invalid signal specification", spec);
+                    ctx.stderr.write_line(&msg).ok();
+                    status = 1;
+                }
+            }
+        }
+        Ok(status)
+    }
+
+    /// The process ID a target names: a PID, a negative process group ID
+    /// or a `%job` spec, which names the job's process group.
+    fn target(&self, arg: &str, ctx: &CommandContext) -> Result<libc::pid_t, String> {
+        if let Some(spec) = arg.strip_prefix('%') {
+            return ctx.jobs.find(spec).map(|job| -job.pid);
+        }
+        arg.parse()
+            .map_err(|_| format!("{}: arguments must be process or job IDs", arg))
+    }
+}
+
+/// The targets after a signal option, which may be set off with `--` so
+/// that a process group ID is not read as a signal.
+fn operands<'a>(args: &'a [&'a str]) -> &'a [&'a str] {
+    args.strip_prefix(&["--"][..]).unwrap_or(args)
+}
+
+impl Command for KillCommand {
+    fn name(&self) -> &'static str {
+        "kill"
+    }
+
+    fn description(&self) -> &'static str {
+        "Send a signal to a job or process"
+    }
+
+    fn usage(&self) -> &'static str {
+        "kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Sends the signal SIGSPEC, or SIGTERM if none is given, to each process
+named by PID or JOBSPEC. A negative PID names a process group. A JOBSPEC
+is `%' followed by a job number, `%' or `+' for the current job, `-' for
+the previous one, the start of its command, or `?' and a string in it.
+Each background job runs in a process group of its own, and a JOBSPEC
+signals the whole group.
+
+Options:
+  -s SIG  the signal to send, by name
+  -n SIG  the signal to send, by number
+  -l      list the signal names; with arguments, translate each between
+          name and number, reading numbers above 128 as exit statuses
+
+Exit Status:
+Returns 0 unless an invalid option is given or a signal could not be
+sent to some target."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut sig = libc::SIGTERM;
+        let mut targets = args;
+        match args {
+            ["-l" | "-L", specs @ ..] => return self.list(specs, ctx),
+            ["--", rest @ ..] => targets = rest,
+            [opt @ ("-s" | "-n"), rest @ ..] => {
+                let spec = rest.first().ok_or_else(|| {
+                    ShellError::usage(self, format!("{}: option requires an argument", opt))
+                })?;
+                sig = signal::number(spec).ok_or_else(|| {
+                    ShellError::failed(1, format!("{}: invalid signal specification", spec))
+                })?;
+                targets = operands(&rest[1..]);
+            }
+            [opt, rest @ ..] if opt.len() > 1 && opt.starts_with('-') => {
+                sig = signal::number(&opt[1..]).ok_or_else(|| {
+                    ShellError::failed(1, format!("{}: invalid signal specification", &opt[1..]))
+                })?;
+                targets = operands(rest);
+            }
+            _ => {}
+        }
+        if targets.is_empty() {
+            return Err(ShellError::failed(2, format!("usage: {}", self.usage())));
+        }
+
+        let mut status = 0;
+        for arg in targets {
+            let sent = self.target(arg, ctx).and_then(|pid| {
+                if unsafe { libc::kill(pid, sig) } == 0 {
+                    Ok(())
+                } else {
+                    let err = io::Error::last_os_error();
+                    Err(format!("({}) - {}", pid, error_message(&err)))
+                }
+            });
+            if let Err(msg) = sent {
+                ctx.stderr.write_line(&format!("kill: {}", msg)).ok();
+                status = 1;
+            }
+        }
+        Ok(status)
+    }
+}
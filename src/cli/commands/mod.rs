@@ -1,20 +1,67 @@
 use crate::cli::registry::CommandRegistry;
 use std::rc::Rc;
 
+pub mod builtin;
 pub mod cd;
+pub mod command;
+pub mod declare;
+pub mod dirs;
 pub mod echo;
 pub mod exit;
+pub mod export;
+pub mod hash;
+pub mod help;
+pub mod kill;
+pub mod printf;
 pub mod pwd;
+pub mod read;
+pub mod set;
+pub mod shift;
+pub mod test;
+pub mod trap;
 pub mod type_cmd;
+pub mod unset;
 
 use crate::cli::commands::cd::CdCommand;
+use crate::cli::commands::declare::{
+    DeclareCommand, LocalCommand, ReadonlyCommand, TypesetCommand,
+};
+use crate::cli::commands::dirs::{DirsCommand, PopdCommand, PushdCommand};
 use crate::cli::commands::echo::EchoCommand;
 use crate::cli::commands::exit::ExitCommand;
+use crate::cli::commands::export::ExportCommand;
+use crate::cli::commands::hash::HashCommand;
+use crate::cli::commands::kill::KillCommand;
+use crate::cli::commands::printf::PrintfCommand;
 use crate::cli::commands::pwd::PwdCommand;
+use crate::cli::commands::read::ReadCommand;
+use crate::cli::commands::set::SetCommand;
+use crate::cli::commands::shift::ShiftCommand;
+use crate::cli::commands::test::{BracketCommand, TestCommand};
+use crate::cli::commands::trap::TrapCommand;
+use crate::cli::commands::unset::UnsetCommand;
 
 pub fn register_all(registry: &mut CommandRegistry) {
     registry.register(Rc::new(ExitCommand));
     registry.register(Rc::new(EchoCommand));
+    registry.register(Rc::new(PrintfCommand));
+    registry.register(Rc::new(ReadCommand));
     registry.register(Rc::new(PwdCommand));
     registry.register(Rc::new(CdCommand));
+    registry.register(Rc::new(SetCommand));
+    registry.register(Rc::new(ExportCommand));
+    registry.register(Rc::new(DeclareCommand));
+    registry.register(Rc::new(TypesetCommand));
+    registry.register(Rc::new(ReadonlyCommand));
+    registry.register(Rc::new(LocalCommand));
+    registry.register(Rc::new(UnsetCommand));
+    registry.register(Rc::new(ShiftCommand));
+    registry.register(Rc::new(TestCommand));
+    registry.register(Rc::new(BracketCommand));
+    registry.register(Rc::new(HashCommand));
+    registry.register(Rc::new(PushdCommand));
+    registry.register(Rc::new(PopdCommand));
+    registry.register(Rc::new(DirsCommand));
+    registry.register(Rc::new(KillCommand));
+    registry.register(Rc::new(TrapCommand));
 }
@@ -0,0 +1,488 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::utils::escape::{self, Escape};
+use crate::utils::quote::shell_quote;
+use std::ffi::{CStr, CString};
+use std::io;
+
+pub struct PrintfCommand;
+
+/// One `%` conversion with its flags, width and precision.
+struct Spec {
+    flags: String,
+    width: Option<i64>,
+    precision: Option<i64>,
+    conversion: char,
+}
+
+/// The state of one `printf` run: arguments still to be used, the output
+/// so far and whether any argument failed to convert.
+struct Formatter<'a> {
+    args: &'a [&'a str],
+    next: usize,
+    out: Vec<u8>,
+    errors: Vec<String>,
+    stopped: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.next).copied();
+        self.next += 1;
+        arg
+    }
+
+    fn int_arg(&mut self) -> i64 {
+        let arg = self.next_arg().unwrap_or("");
+        parse_int(arg).unwrap_or_else(|(partial, reason)| {
+            self.errors.push(format!("{}: {}", arg, reason));
+            partial
+        })
+    }
+
+    /// Takes a `*` width or precision from the next argument. It has to
+    /// fit the C library's `int`.
+    fn field_arg(&mut self, what: &str) -> Result<i64, String> {
+        let arg = self.args.get(self.next).copied().unwrap_or("");
+        let n = self.int_arg();
+        if i32::try_from(n).is_err() {
+            return Err(format!("{}: invalid {}", arg, what));
+        }
+        Ok(n)
+    }
+
+    fn float_arg(&mut self) -> f64 {
+        let arg = self.next_arg().unwrap_or("");
+        parse_float(arg).unwrap_or_else(|partial| {
+            self.errors.push(format!("{}: invalid number", arg));
+            partial
+        })
+    }
+
+    /// Formats the whole `format` string once, returning an error for a
+    /// malformed conversion.
+    fn run(&mut self, format: &str) -> Result<(), String> {
+        let mut i = 0;
+        while i < format.len() && !self.stopped {
+            let rest = &format[i..];
+            let c = rest.chars().next().unwrap();
+            if c == '\\' {
+                match escape::decode(&rest[1..], false) {
+                    Some((Escape::Bytes(b), used)) => {
+                        self.out.extend(b);
+                        i += 1 + used;
+                    }
+                    Some((Escape::Stop, _)) => self.stopped = true,
+                    None => {
+                        self.out.push(b'\\');
+                        i += 1;
+                    }
+                }
+            } else if c == '%' {
+                i += 1 + self.conversion(&rest[1..])?;
+            } else {
+                let mut buf = [0; 4];
+                self.out.extend(c.encode_utf8(&mut buf).as_bytes());
+                i += c.len_utf8();
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the conversion whose text (after the `%`) starts `s`,
+    /// returning how many bytes it used.
+    fn conversion(&mut self, s: &str) -> Result<usize, String> {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        let mut spec = Spec {
+            flags: String::new(),
+            width: None,
+            precision: None,
+            conversion: '%',
+        };
+        while i < bytes.len() && b"-+ #0".contains(&bytes[i]) {
+            spec.flags.push(bytes[i] as char);
+            i += 1;
+        }
+        if bytes.get(i) == Some(&b'*') {
+            // a negative width left-justifies
+            let width = self.field_arg("field width")?;
+            if width < 0 {
+                spec.flags.push('-');
+            }
+            spec.width = Some(width.abs());
+            i += 1;
+        } else {
+            let digits = s[i..].bytes().take_while(u8::is_ascii_digit).count();
+            spec.width = match &s[i..i + digits] {
+                "" => None,
+                text => Some(field_width(text, "field width")?),
+            };
+            i += digits;
+        }
+        if bytes.get(i) == Some(&b'.') {
+            i += 1;
+            if bytes.get(i) == Some(&b'*') {
+                // a negative precision counts as none given
+                spec.precision = Some(self.field_arg("precision")?).filter(|p| *p >= 0);
+                i += 1;
+            } else {
+                let digits = s[i..].bytes().take_while(u8::is_ascii_digit).count();
+                spec.precision = match &s[i..i + digits] {
+                    "" => Some(0),
+                    text => Some(field_width(text, "precision")?),
+                };
+                i += digits;
+            }
+        }
+        // length modifiers mean nothing here
+        while i < bytes.len() && b"hlLjzt".contains(&bytes[i]) {
+            i += 1;
+        }
+        spec.conversion = match s[i..].chars().next() {
+            Some(c) => c,
+            None => return Err("`%': missing format character".into()),
+        };
+        i += spec.conversion.len_utf8();
+
+        match spec.conversion {
+            '%' => self.out.push(b'%'),
+            's' => {
+                let arg = self.next_arg().unwrap_or("");
+                self.pad_text(&spec, arg.as_bytes());
+            }
+            'b' => {
+                let arg = self.next_arg().unwrap_or("");
+                let (bytes, stopped) = escape::unescape(arg, true);
+                self.pad_text(&spec, &bytes);
+                self.stopped |= stopped;
+            }
+            'q' => {
+                let arg = self.next_arg().unwrap_or("");
+                self.pad_text(&spec, shell_quote(arg).as_bytes());
+            }
+            'c' => {
+                let arg = self.next_arg().unwrap_or("");
+                let first = arg.chars().next().map(String::from).unwrap_or_default();
+                let spec = Spec {
+                    precision: None,
+                    ..spec
+                };
+                self.pad_text(&spec, first.as_bytes());
+            }
+            'd' | 'i' | 'u' | 'o' | 'x' | 'X' => {
+                let n = self.int_arg();
+                self.out.extend(c_format(&spec, "ll", Value::Int(n))?);
+            }
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => {
+                let n = self.float_arg();
+                self.out.extend(c_format(&spec, "", Value::Float(n))?);
+            }
+            other => return Err(format!("`{}': invalid format character", other)),
+        }
+        Ok(i)
+    }
+
+    /// Writes `text`, cut to the precision and padded to the width.
+    fn pad_text(&mut self, spec: &Spec, text: &[u8]) {
+        let text = match spec.precision {
+            Some(p) if p >= 0 && (p as usize) < text.len() => &text[..p as usize],
+            _ => text,
+        };
+        let width = spec.width.unwrap_or(0);
+        let left = spec.flags.contains('-') || width < 0;
+        let pad = (width.unsigned_abs() as usize).saturating_sub(text.len());
+        if !left {
+            self.out.extend(std::iter::repeat(b' ').take(pad));
+        }
+        self.out.extend_from_slice(text);
+        if left {
+            self.out.extend(std::iter::repeat(b' ').take(pad));
+        }
+    }
+}
+
+enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+/// Formats a number with the C library, which already implements every
+/// numeric conversion and flag exactly as `printf(1)` needs. Fails when
+/// the result would be too long for it.
+fn c_format(spec: &Spec, length: &str, value: Value) -> Result<Vec<u8>, String> {
+    let mut fmt = format!("%{}", spec.flags);
+    if let Some(w) = spec.width {
+        fmt.push_str(&w.to_string());
+    }
+    if let Some(p) = spec.precision {
+        fmt.push_str(&format!(".{}", p));
+    }
+    fmt.push_str(length);
+    fmt.push(spec.conversion);
+    let fmt = CString::new(fmt).unwrap_or_default();
+
+    let mut buf = vec![0u8; 512];
+    loop {
+        let len = unsafe {
+            let ptr = buf.as_mut_ptr() as *mut libc::c_char;
+            match value {
+                Value::Int(n) => {
+                    libc::snprintf(ptr, buf.len(), fmt.as_ptr(), n as libc::c_longlong)
+                }
+                Value::Float(n) => libc::snprintf(ptr, buf.len(), fmt.as_ptr(), n),
+            }
+        };
+        if len < 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        if (len as usize) < buf.len() {
+            let text = unsafe { CStr::from_ptr(buf.as_ptr() as *const libc::c_char) };
+            return Ok(text.to_bytes().to_vec());
+        }
+        buf.resize(len as usize + 1, 0);
+    }
+}
+
+/// A width or precision written into the format, which has to fit the C
+/// library's `int`.
+fn field_width(text: &str, what: &str) -> Result<i64, String> {
+    text.parse::<i32>()
+        .map(i64::from)
+        .map_err(|_| format!("{}: invalid {}", text, what))
+}
+
+/// Parses an integer argument the way `printf(1)` does: decimal, `0x` hex,
+/// `0` octal, or `'c` for the code of character `c`. On failure, returns
+/// the value of the longest valid prefix, clamped to the range of `i64`,
+/// with the reason.
+fn parse_int(arg: &str) -> Result<i64, (i64, &'static str)> {
+    if let Some(c) = arg.strip_prefix(['\'', '"']) {
+        return Ok(c.chars().next().map_or(0, |c| c as i64));
+    }
+    let text = arg.trim_start();
+    if text.is_empty() {
+        return if arg.is_empty() {
+            Ok(0)
+        } else {
+            Err((0, "invalid number"))
+        };
+    }
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (radix, digits) = match unsigned.strip_prefix("0x").or(unsigned.strip_prefix("0X")) {
+        Some(hex) => (16, hex),
+        None if unsigned.len() > 1 && unsigned.starts_with('0') => (8, &unsigned[1..]),
+        None => (10, unsigned),
+    };
+    let used = digits.chars().take_while(|c| c.is_digit(radix)).count();
+    let magnitude = digits[..used].chars().fold(0u128, |n, c| {
+        n.saturating_mul(u128::from(radix))
+            .saturating_add(u128::from(c.to_digit(radix).unwrap_or(0)))
+    });
+    let value = if negative {
+        i64::try_from(-i128::try_from(magnitude).unwrap_or(i128::MAX)).ok()
+    } else {
+        i64::try_from(magnitude).ok()
+    };
+    match value {
+        None if negative => Err((i64::MIN, "Numerical result out of range")),
+        None => Err((i64::MAX, "Numerical result out of range")),
+        Some(value) if used == 0 || used < digits.len() => Err((value, "invalid number")),
+        Some(value) => Ok(value),
+    }
+}
+
+/// Parses a floating-point argument, with the same conventions as
+/// [`parse_int`].
+fn parse_float(arg: &str) -> Result<f64, f64> {
+    if let Some(c) = arg.strip_prefix(['\'', '"']) {
+        return Ok(c.chars().next().map_or(0.0, |c| c as u32 as f64));
+    }
+    let text = arg.trim_start();
+    if text.is_empty() {
+        return if arg.is_empty() { Ok(0.0) } else { Err(0.0) };
+    }
+    let c_text = CString::new(text).map_err(|_| 0.0)?;
+    let mut end: *mut libc::c_char = std::ptr::null_mut();
+    let value = unsafe { libc::strtod(c_text.as_ptr(), &mut end) };
+    let used = end as usize - c_text.as_ptr() as usize;
+    if used == text.len() {
+        Ok(value)
+    } else {
+        Err(value)
+    }
+}
+
+impl Command for PrintfCommand {
+    fn name(&self) -> &'static str {
+        "printf"
+    }
+
+    fn description(&self) -> &'static str {
+        "Format and print arguments"
+    }
+
+    fn usage(&self) -> &'static str {
+        "printf [-v var] format [arguments]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Writes ARGUMENTS to standard output as directed by FORMAT, which holds
+plain characters, backslash escapes and conversions of the form
+%[flags][width][.precision]conversion.
+
+Conversions:
+  %d %i      signed decimal integer
+  %u %o %x %X
+             unsigned decimal, octal and hexadecimal integer
+  %f %e %g   floating point, also %F %E %G %a %A
+  %s %c      string and first character
+  %b         string with backslash escapes expanded as by `echo -e'
+  %q         string quoted so the shell reads it back unchanged
+  %%         a literal percent sign
+
+A width or precision of `*' is taken from the next argument; a negative
+width left-justifies and a negative precision is ignored. Numeric
+arguments may be written as 0x hex, 0 octal or 'c for a character code,
+and are clamped to the range of a 64-bit integer.
+FORMAT is reused as often as needed to consume every argument.
+
+Options:
+  -v var  assign the output to the shell variable VAR instead of
+          printing it
+
+Exit Status:
+Returns 0 unless an invalid option is given, FORMAT is malformed or an
+argument cannot be converted."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut args = args;
+        let mut var = None;
+        match args.first() {
+            Some(&"-v") => match args.get(1) {
+                Some(name) => {
+                    var = Some(*name);
+                    args = &args[2..];
+                }
+                None => return Err(ShellError::usage(self, "-v: option requires an argument")),
+            },
+            Some(&"--") => args = &args[1..],
+            _ => {}
+        }
+        if args.first() == Some(&"--") {
+            args = &args[1..];
+        }
+        let Some((format, rest)) = args.split_first() else {
+            return Err(ShellError::failed(2, format!("usage: {}", self.usage())));
+        };
+
+        let mut f = Formatter {
+            args: rest,
+            next: 0,
+            out: vec![],
+            errors: vec![],
+            stopped: false,
+        };
+        let mut result = f.run(format);
+        // reuse the format while it keeps consuming arguments
+        while result.is_ok() && !f.stopped && f.next > 0 && f.next < rest.len() {
+            result = f.run(format);
+        }
+
+        for err in &f.errors {
+            ctx.stderr
+                .write_line(format!("printf: {}", err).as_str())
+                .ok();
+        }
+        if let Err(e) = &result {
+            ctx.stderr
+                .write_line(format!("printf: {}", e).as_str())
+                .ok();
+        }
+        let status = i32::from(!f.errors.is_empty() || result.is_err());
+        match var {
+            Some(name) => {
+                let value = String::from_utf8_lossy(&f.out).into_owned();
+                ctx.vars.set(name, value)?;
+            }
+            None => ctx.write_stdout(&f.out)?,
+        }
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printf(format: &str, args: &[&str]) -> (String, usize) {
+        let mut f = Formatter {
+            args,
+            next: 0,
+            out: vec![],
+            errors: vec![],
+            stopped: false,
+        };
+        f.run(format).unwrap();
+        while f.next > 0 && f.next < args.len() {
+            f.run(format).unwrap();
+        }
+        (String::from_utf8(f.out).unwrap(), f.errors.len())
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(
+            printf("%5s|%-4d|%03x\\n", &["ab", "7", "255"]).0,
+            "   ab|7   |0ff\n"
+        );
+        assert_eq!(
+            printf("%.2f %e %g", &["3.14159", "1500", "0.0001"]).0,
+            "3.14 1.500000e+03 0.0001"
+        );
+        assert_eq!(printf("%*d|%.*s", &["4", "5", "2", "abc"]).0, "   5|ab");
+        assert_eq!(printf("%c%b%%", &["xyz", "a\\tb"]).0, "xa\tb%");
+        assert_eq!(
+            printf("%d %o %X %u", &["'A", "010", "0x1f", "-1"]).0,
+            "65 10 1F 18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn format_reuse_and_errors() {
+        assert_eq!(printf("<%s>", &["a", "b", "c"]).0, "<a><b><c>");
+        assert_eq!(printf("%q", &["a b"]).0, "'a b'");
+        assert_eq!(printf("%s=%s ", &["a", "1", "b"]).0, "a=1 b= ");
+        assert_eq!(printf("%d", &["12abc"]), ("12".to_string(), 1));
+        assert_eq!(
+            printf("%d", &["99999999999999999999"]),
+            ("9223372036854775807".to_string(), 1)
+        );
+        assert_eq!(
+            printf("%d", &["-0x8000000000000001"]),
+            ("-9223372036854775808".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn star_width_and_precision() {
+        assert_eq!(printf("%.*f|", &["-5", "1.5"]).0, "1.500000|");
+        assert_eq!(printf("%*d|%*s|", &["-4", "5", "-3", "a"]).0, "5   |a  |");
+        let mut f = Formatter {
+            args: &["99999999999", "1"],
+            next: 0,
+            out: vec![],
+            errors: vec![],
+            stopped: false,
+        };
+        assert_eq!(
+            f.run("%*d"),
+            Err("99999999999: invalid field width".to_string())
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::utils::{paths, sys};
 use std::env;
 
 pub struct PwdCommand;
@@ -12,20 +14,45 @@ impl Command for PwdCommand {
         "Print the current working directory"
     }
 
-    fn execute(&self, _args: &[&str], ctx: &mut CommandContext) {
-        if let Some(pwd) = ctx.env.get("PWD") {
-            ctx.stdout.write_line(format!("{}", pwd).as_str()).ok();
-            return;
+    fn usage(&self) -> &'static str {
+        "pwd [-LP]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Prints the absolute pathname of the current working directory.
+
+Options:
+  -L  print PWD if it names the current directory, keeping symbolic
+      links (the default)
+  -P  print the physical directory, with symbolic links resolved
+      (the default under `set -o physical')
+
+Exit Status:
+Returns 0 unless the current directory cannot be read."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut physical = ctx.options.physical;
+        for arg in args {
+            match *arg {
+                "-L" => physical = false,
+                "-P" => physical = true,
+                _ => return Err(ShellError::usage(self, format!("{}: invalid option", arg))),
+            }
+        }
+        if !physical {
+            if let Some(pwd) = ctx.get_var("PWD").filter(|pwd| paths::is_valid_pwd(pwd)) {
+                ctx.write_stdout_line(&pwd)?;
+                return Ok(0);
+            }
         }
         match env::current_dir() {
             Ok(path) => {
-                ctx.stdout
-                    .write_line(format!("{}", path.display()).as_str())
-                    .ok();
-            }
-            Err(e) => {
-                ctx.stdout.write_line(format!("pwd: {}", e).as_str()).ok();
+                ctx.write_stdout_line(format!("{}", path.display()).as_str())?;
+                Ok(0)
             }
+            Err(e) => Err(ShellError::failed(1, sys::error_message(&e))),
         }
     }
 }
@@ -0,0 +1,364 @@
+use crate::cli::command::{Command, CommandContext, IoHandle};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::vars::is_name;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const DEFAULT_IFS: &str = " \t\n";
+
+pub struct ReadCommand;
+
+/// Options controlling a single `read`.
+struct ReadOptions {
+    raw: bool,
+    prompt: Option<String>,
+    silent: bool,
+    /// `-n`: stop after this many characters or at the delimiter.
+    max_chars: Option<usize>,
+    /// `-N`: read exactly this many characters, ignoring the delimiter.
+    exact_chars: Option<usize>,
+    delim: u8,
+    timeout: Option<Duration>,
+    /// `-a`: assign the fields to this array instead.
+    array: Option<String>,
+}
+
+/// How a read ended.
+enum Ending {
+    Delimiter,
+    Eof,
+    Timeout,
+}
+
+/// Turns off terminal echo for `-s` and puts it back when dropped.
+struct SilentTerminal {
+    fd: i32,
+    saved: libc::termios,
+}
+
+impl SilentTerminal {
+    fn new(fd: i32) -> Option<Self> {
+        unsafe {
+            if libc::isatty(fd) != 1 {
+                return None;
+            }
+            let mut saved: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut saved) != 0 {
+                return None;
+            }
+            let mut quiet = saved;
+            quiet.c_lflag &= !libc::ECHO;
+            libc::tcsetattr(fd, libc::TCSANOW, &quiet);
+            Some(SilentTerminal { fd, saved })
+        }
+    }
+}
+
+impl Drop for SilentTerminal {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.saved);
+        }
+    }
+}
+
+/// Waits until `fd` has input or `deadline` passes, returning whether input
+/// is available.
+fn wait_readable(fd: i32, deadline: Instant) -> bool {
+    let left = deadline.saturating_duration_since(Instant::now());
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ms = left.as_millis().min(i32::MAX as u128) as i32;
+    unsafe { libc::poll(&mut pfd, 1, ms) > 0 }
+}
+
+/// Reads one logical line as characters, each marked with whether a
+/// backslash protected it from field splitting.
+fn read_input(input: &mut IoHandle, opts: &ReadOptions) -> (Vec<(char, bool)>, Ending) {
+    let fd = input.raw_fd();
+    let deadline = opts.timeout.map(|t| Instant::now() + t);
+    let limit = opts.exact_chars.or(opts.max_chars);
+    let mut chars = vec![];
+    let mut escaped = false;
+
+    let next_byte = |input: &mut IoHandle| -> Result<Option<u8>, Ending> {
+        if let (Some(fd), Some(deadline)) = (fd, deadline) {
+            if !wait_readable(fd, deadline) {
+                return Err(Ending::Timeout);
+            }
+        }
+        input.read_byte().map_err(|_| Ending::Eof)
+    };
+
+    loop {
+        if limit.is_some_and(|n| chars.len() >= n) {
+            return (chars, Ending::Delimiter);
+        }
+        let byte = match next_byte(input) {
+            Ok(Some(b)) => b,
+            Ok(None) => return (chars, Ending::Eof),
+            Err(ending) => return (chars, ending),
+        };
+        // gather the rest of a multi-byte character
+        let mut buf = vec![byte];
+        let len = match byte {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        while buf.len() < len {
+            match next_byte(input) {
+                Ok(Some(b)) => buf.push(b),
+                _ => break,
+            }
+        }
+        let c = String::from_utf8_lossy(&buf)
+            .chars()
+            .next()
+            .unwrap_or('\u{fffd}');
+
+        if escaped {
+            escaped = false;
+            if c != '\n' {
+                chars.push((c, true));
+            }
+            continue;
+        }
+        if opts.exact_chars.is_none() && byte == opts.delim {
+            return (chars, Ending::Delimiter);
+        }
+        if c == '\\' && !opts.raw {
+            escaped = true;
+            continue;
+        }
+        chars.push((c, false));
+    }
+}
+
+/// Splits `chars` into one value per name: each name but the last gets a
+/// field, and the last gets the rest of the line.
+fn split_fields(chars: &[(char, bool)], ifs: &str, count: usize) -> Vec<String> {
+    let is_ifs = |&(c, esc): &(char, bool)| !esc && ifs.contains(c);
+    let is_space = |p: &(char, bool)| is_ifs(p) && p.0.is_whitespace();
+    let mut i = 0;
+    let mut end = chars.len();
+    while i < end && is_space(&chars[i]) {
+        i += 1;
+    }
+    while end > i && is_space(&chars[end - 1]) {
+        end -= 1;
+    }
+
+    let mut values = vec![];
+    while values.len() + 1 < count && i < end {
+        let start = i;
+        while i < end && !is_ifs(&chars[i]) {
+            i += 1;
+        }
+        values.push(chars[start..i].iter().map(|p| p.0).collect());
+        while i < end && is_space(&chars[i]) {
+            i += 1;
+        }
+        if i < end && is_ifs(&chars[i]) {
+            i += 1;
+            while i < end && is_space(&chars[i]) {
+                i += 1;
+            }
+        }
+    }
+    values.push(chars[i..end].iter().map(|p| p.0).collect());
+    values.resize(count, String::new());
+    values
+}
+
+impl Command for ReadCommand {
+    fn name(&self) -> &'static str {
+        "read"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read a line from standard input into variables"
+    }
+
+    fn usage(&self) -> &'static str {
+        "read [-rs] [-a array] [-d delim] [-n nchars] [-N nchars] [-p prompt] [-t timeout] [name ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Reads a line from standard input and splits it into fields using IFS.
+The first field is assigned to the first NAME, the second to the second
+NAME and so on, with the rest of the line assigned to the last NAME.
+With no NAMEs, the line is stored in REPLY.
+
+Options:
+  -a array    assign the fields to the indexed array ARRAY
+  -d delim    end the line at the first character of DELIM, not newline
+  -n nchars   return after NCHARS characters or at the delimiter
+  -N nchars   return after exactly NCHARS characters, ignoring the
+              delimiter and without splitting them into fields
+  -p prompt   print PROMPT to standard error first, if reading from a
+              terminal
+  -r          do not treat backslashes as escape characters
+  -s          do not echo input coming from a terminal
+  -t timeout  fail if a whole line is not read within TIMEOUT seconds,
+              which may be fractional
+
+Exit Status:
+Returns 0 unless end of file is reached, the read times out (status
+greater than 128) or an invalid option is given."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut opts = ReadOptions {
+            raw: false,
+            prompt: None,
+            silent: false,
+            max_chars: None,
+            exact_chars: None,
+            delim: b'\n',
+            timeout: None,
+            array: None,
+        };
+        let mut rest = args;
+        while let Some(flags) = rest.first().and_then(|a| a.strip_prefix('-')) {
+            rest = &rest[1..];
+            if flags == "-" {
+                break;
+            }
+            for (i, flag) in flags.char_indices() {
+                if flag == 'r' || flag == 's' {
+                    if flag == 'r' {
+                        opts.raw = true;
+                    } else {
+                        opts.silent = true;
+                    }
+                    continue;
+                }
+                if !"adnNpt".contains(flag) {
+                    return Err(ShellError::usage(
+                        self,
+                        format!("-{}: invalid option", flag),
+                    ));
+                }
+                // the value is the rest of this word or the next word
+                let value = if i + 1 < flags.len() {
+                    flags[i + 1..].to_string()
+                } else if let Some((v, tail)) = rest.split_first() {
+                    rest = tail;
+                    v.to_string()
+                } else {
+                    let msg = format!("-{}: option requires an argument", flag);
+                    return Err(ShellError::usage(self, msg));
+                };
+                match flag {
+                    'a' => opts.array = Some(value),
+                    'd' => opts.delim = value.bytes().next().unwrap_or(0),
+                    'p' => opts.prompt = Some(value),
+                    'n' | 'N' => match value.parse::<usize>() {
+                        Ok(n) if flag == 'n' => opts.max_chars = Some(n),
+                        Ok(n) => opts.exact_chars = Some(n),
+                        Err(_) => {
+                            return Err(ShellError::failed(1, format!("{}: invalid number", value)))
+                        }
+                    },
+                    _ => match value.parse::<f64>() {
+                        Ok(secs) if secs >= 0.0 && secs.is_finite() => {
+                            opts.timeout = Some(Duration::from_secs_f64(secs))
+                        }
+                        _ => {
+                            let msg = format!("{}: invalid timeout specification", value);
+                            return Err(ShellError::failed(1, msg));
+                        }
+                    },
+                }
+                break;
+            }
+        }
+        if let Some(bad) = rest
+            .iter()
+            .chain(&opts.array.as_deref())
+            .find(|n| !is_name(n))
+        {
+            let msg = format!("`{}': not a valid identifier", bad);
+            return Err(ShellError::failed(1, msg));
+        }
+
+        let fd = ctx.stdin.raw_fd();
+        let on_terminal = fd.is_some_and(|fd| unsafe { libc::isatty(fd) } == 1);
+        // -t 0 only reports whether input is waiting
+        if opts.timeout == Some(Duration::ZERO) {
+            let ready = fd.is_some_and(|fd| wait_readable(fd, Instant::now()));
+            return Ok(if ready { 0 } else { 1 });
+        }
+        if let Some(prompt) = opts.prompt.as_ref().filter(|_| on_terminal) {
+            ctx.stderr.write_all(prompt.as_bytes()).ok();
+        }
+
+        let silence = match fd {
+            Some(fd) if opts.silent => SilentTerminal::new(fd),
+            _ => None,
+        };
+        let mut input = ctx.stdin.clone();
+        let (chars, ending) = read_input(&mut input, &opts);
+        drop(silence);
+
+        // with -N the characters are kept exactly as read, unsplit
+        let ifs = match opts.exact_chars {
+            Some(_) => String::new(),
+            None => ctx.get_var("IFS").unwrap_or_else(|| DEFAULT_IFS.into()),
+        };
+        if let Some(array) = &opts.array {
+            // as many fields as there are, without padding
+            let mut fields = split_fields(&chars, &ifs, chars.len() + 1);
+            while fields.last().is_some_and(String::is_empty) {
+                fields.pop();
+            }
+            let items = fields.into_iter().map(|f| (None, f)).collect();
+            ctx.vars.assign_array(array, items, false)?;
+        } else if rest.is_empty() {
+            let line: String = chars.iter().map(|p| p.0).collect();
+            ctx.vars.set("REPLY", line)?;
+        } else {
+            for (name, value) in rest.iter().zip(split_fields(&chars, &ifs, rest.len())) {
+                ctx.vars.set(name, value)?;
+            }
+        }
+        Ok(match ending {
+            Ending::Delimiter => 0,
+            Ending::Eof => 1,
+            Ending::Timeout => 128 + libc::SIGALRM,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(line: &str, ifs: &str, count: usize) -> Vec<String> {
+        let chars: Vec<(char, bool)> = line.chars().map(|c| (c, false)).collect();
+        split_fields(&chars, ifs, count)
+    }
+
+    #[test]
+    fn field_splitting() {
+        assert_eq!(split("  a  b c  ", DEFAULT_IFS, 2), vec!["a", "b c"]);
+        assert_eq!(split("a", DEFAULT_IFS, 3), vec!["a", "", ""]);
+        assert_eq!(split("x:y::z", ":", 4), vec!["x", "y", "", "z"]);
+        assert_eq!(split("x:y:z", ":", 2), vec!["x", "y:z"]);
+
+        let escaped = vec![
+            ('a', false),
+            (' ', true),
+            ('b', false),
+            (' ', false),
+            ('c', false),
+        ];
+        assert_eq!(split_fields(&escaped, DEFAULT_IFS, 2), vec!["a b", "c"]);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::options::{ShellOptions, OPTIONS};
+use crate::cli::vars::Value;
+use crate::utils::quote::shell_quote;
+
+pub struct SetCommand;
+
+impl SetCommand {
+    fn list_variables(ctx: &mut CommandContext) -> Result<(), ShellError> {
+        let vars: Vec<_> = ctx
+            .vars
+            .iter()
+            .filter_map(|(name, var)| Some((name.to_string(), var.value.clone()?)))
+            .collect();
+        for (name, value) in vars {
+            let value = match value {
+                Value::Scalar(s) => shell_quote(&s),
+                array => array.to_assignment(),
+            };
+            ctx.write_stdout_line(format!("{}={}", name, value).as_str())?;
+        }
+        Ok(())
+    }
+
+    /// `set -o` prints a table of options, `set +o` prints the commands that
+    /// recreate the current settings.
+    fn list_options(ctx: &mut CommandContext, reinput: bool) -> Result<(), ShellError> {
+        for (name, _) in OPTIONS {
+            let on = ctx.options.get(name).unwrap_or(false);
+            let line = if reinput {
+                format!("set {}o {}", if on { '-' } else { '+' }, name)
+            } else {
+                format!("{:<15}\t{}", name, if on { "on" } else { "off" })
+            };
+            ctx.write_stdout_line(&line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Command for SetCommand {
+    fn name(&self) -> &'static str {
+        "set"
+    }
+
+    fn description(&self) -> &'static str {
+        "Set or unset shell options and positional parameters"
+    }
+
+    fn usage(&self) -> &'static str {
+        "set [-CPefnux] [-o option-name] [--] [arg ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Sets or unsets shell options. Using + rather than - turns an option
+off. Any ARGs left after the options, or after `--', become the
+positional parameters $1, $2 and so on; `set --' alone unsets them. With
+no arguments, prints the names and values of all shell variables.
+
+Options:
+  -C  do not let > overwrite existing files (noclobber)
+  -P  resolve symbolic links when changing directory (physical)
+  -e  exit as soon as a command fails (errexit)
+  -f  disable pathname expansion (noglob)
+  -n  read commands without running them (noexec)
+  -u  treat unset variables as an error (nounset)
+  -x  print commands as they are run (xtrace)
+  -o option-name
+      set the named option; -o alone lists the current settings
+      and +o prints them as commands that recreate them
+
+Options without a letter, set only with -o:
+  huponexit  send SIGHUP to background jobs when the shell exits
+  pipefail   a pipeline fails if any of its commands fails"
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        if args.is_empty() {
+            Self::list_variables(ctx)?;
+            return Ok(0);
+        }
+
+        let mut positional = None;
+        let mut iter = args.iter();
+        loop {
+            // the first word that is not an option starts the parameters
+            let rest = iter.as_slice();
+            let Some(arg) = iter.next() else {
+                break;
+            };
+            if *arg == "--" || *arg == "-" {
+                positional = Some(iter.as_slice());
+                break;
+            }
+            let (on, flags) = match (arg.strip_prefix('-'), arg.strip_prefix('+')) {
+                (Some(flags), _) => (true, flags),
+                (_, Some(flags)) => (false, flags),
+                _ => {
+                    positional = Some(rest);
+                    break;
+                }
+            };
+            for flag in flags.chars() {
+                if flag == 'o' {
+                    match iter.next() {
+                        Some(name) if ctx.options.set(name, on) => {}
+                        Some(name) => {
+                            return Err(ShellError::failed(
+                                1,
+                                format!("{}: invalid option name", name),
+                            ))
+                        }
+                        None => Self::list_options(ctx, !on)?,
+                    }
+                    continue;
+                }
+                match ShellOptions::name_for_flag(flag) {
+                    Some(name) => {
+                        ctx.options.set(name, on);
+                    }
+                    None => {
+                        let sign = arg.chars().next().unwrap();
+                        return Err(ShellError::failed(
+                            2,
+                            format!("{}{}: invalid option", sign, flag),
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(args) = positional {
+            ctx.params.positional = args.iter().map(|a| a.to_string()).collect();
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+
+pub struct ShiftCommand;
+
+impl Command for ShiftCommand {
+    fn name(&self) -> &'static str {
+        "shift"
+    }
+
+    fn description(&self) -> &'static str {
+        "Shift positional parameters"
+    }
+
+    fn usage(&self) -> &'static str {
+        "shift [n]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Renames the positional parameters $N+1, $N+2 ... to $1, $2 ... and
+drops the first N. N defaults to 1.
+
+Exit Status:
+Returns 0 unless N is negative or greater than $#."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let n = match args {
+            [] => 1,
+            [n] => match n.parse::<i64>() {
+                Ok(count) => usize::try_from(count).map_err(|_| {
+                    ShellError::failed(1, format!("{}: shift count out of range", n))
+                })?,
+                Err(_) => {
+                    return Err(ShellError::failed(
+                        1,
+                        format!("{}: numeric argument required", n),
+                    ))
+                }
+            },
+            _ => return Err(ShellError::failed(1, "too many arguments")),
+        };
+        Ok(if ctx.params.shift(n) { 0 } else { 1 })
+    }
+}
@@ -0,0 +1,324 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::parser::parse_cond::{BINARY_OPS, UNARY_OPS};
+use std::ffi::CString;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+const HELP: &str = "\
+Evaluates EXPR and exits with status 0 (true) or 1 (false).
+
+File operators:
+  -e FILE  FILE exists (also -a)
+  -f FILE  FILE is a regular file
+  -d FILE  FILE is a directory
+  -L FILE  FILE is a symbolic link (also -h)
+  -b, -c, -p, -S FILE
+           FILE is a block device, character device, named pipe or socket
+  -r, -w, -x FILE
+           FILE is readable, writable or executable by you
+  -s FILE  FILE exists and is not empty
+  -g, -u, -k FILE
+           FILE has its set-group-ID, set-user-ID or sticky bit set
+  -O, -G FILE
+           FILE is owned by your effective user or group ID
+  -N FILE  FILE was modified since it was last read
+  -t FD    file descriptor FD is open on a terminal
+  FILE1 -nt FILE2, FILE1 -ot FILE2
+           FILE1 is newer or older than FILE2 by modification time
+  FILE1 -ef FILE2
+           FILE1 and FILE2 are the same file
+
+String operators:
+  -z STRING, -n STRING
+           STRING is empty or not empty; STRING alone is the same as -n
+  STRING1 = STRING2, STRING1 != STRING2
+           the strings are equal or not equal (== is the same as =)
+  STRING1 < STRING2, STRING1 > STRING2
+           STRING1 sorts before or after STRING2
+
+Other operators:
+  -v VAR   the shell variable VAR is set
+  -o OPTION
+           the shell option OPTION is enabled
+  ! EXPR, EXPR1 -a EXPR2, EXPR1 -o EXPR2, ( EXPR )
+           negation, and, or, grouping
+  ARG1 OP ARG2
+           integer comparison, OP being -eq, -ne, -lt, -le, -gt or -ge
+
+Exit Status:
+Returns 0 if EXPR is true, 1 if it is false and 2 if it is invalid.";
+
+pub struct TestCommand;
+
+/// `[`, which is `test` with a required closing `]`.
+pub struct BracketCommand;
+
+impl Command for TestCommand {
+    fn name(&self) -> &'static str {
+        "test"
+    }
+
+    fn description(&self) -> &'static str {
+        "Evaluate conditional expression"
+    }
+
+    fn usage(&self) -> &'static str {
+        "test [expr]"
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        run(args, ctx)
+    }
+
+    fn accepts_help_flag(&self) -> bool {
+        false
+    }
+}
+
+impl Command for BracketCommand {
+    fn name(&self) -> &'static str {
+        "["
+    }
+
+    fn description(&self) -> &'static str {
+        "Evaluate conditional expression; the last argument must be `]'"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[ arg... ]"
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        match args.split_last() {
+            Some((&"]", rest)) => run(rest, ctx),
+            _ => Err(ShellError::failed(2, "missing `]'")),
+        }
+    }
+
+    fn accepts_help_flag(&self) -> bool {
+        false
+    }
+}
+
+fn run(args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+    match evaluate(args, ctx) {
+        Ok(result) => Ok(i32::from(!result)),
+        Err(e) => Err(ShellError::failed(2, e)),
+    }
+}
+
+/// Evaluates `test` arguments, going by the argument count for up to four
+/// arguments as POSIX specifies, so that `test -n = -n` and the like
+/// compare strings rather than failing to parse.
+fn evaluate(args: &[&str], ctx: &CommandContext) -> Result<bool, String> {
+    match args {
+        [] => Ok(false),
+        [arg] => Ok(!arg.is_empty()),
+        ["!", arg] => Ok(arg.is_empty()),
+        [op, arg] if UNARY_OPS.contains(op) => Ok(unary(op, arg, ctx)),
+        [op, _] => Err(format!("{}: unary operator expected", op)),
+        [lhs, op, rhs] if is_binary(op) => binary(lhs, op, rhs),
+        [lhs, "-a", rhs] => Ok(!lhs.is_empty() && !rhs.is_empty()),
+        [lhs, "-o", rhs] => Ok(!lhs.is_empty() || !rhs.is_empty()),
+        ["!", rest @ ..] if args.len() <= 4 => evaluate(rest, ctx).map(|b| !b),
+        ["(", arg, ")"] => Ok(!arg.is_empty()),
+        ["(", a, b, ")"] => evaluate(&[*a, *b], ctx),
+        [_, op, _] => Err(format!("{}: binary operator expected", op)),
+        _ => {
+            let mut parser = TestParser { args, pos: 0, ctx };
+            let result = parser.or()?;
+            match args.get(parser.pos) {
+                None => Ok(result),
+                Some(_) => Err("too many arguments".into()),
+            }
+        }
+    }
+}
+
+fn is_binary(op: &str) -> bool {
+    op != "=~" && BINARY_OPS.contains(&op)
+}
+
+/// Recursive-descent parser for expressions of five or more arguments,
+/// with `!` binding tighter than `-a`, and `-a` tighter than `-o`.
+struct TestParser<'a> {
+    args: &'a [&'a str],
+    pos: usize,
+    ctx: &'a CommandContext,
+}
+
+impl<'a> TestParser<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        let arg = self.args.get(self.pos).ok_or("argument expected")?;
+        self.pos += 1;
+        Ok(arg)
+    }
+
+    fn peek_is(&self, arg: &str) -> bool {
+        self.args.get(self.pos) == Some(&arg)
+    }
+
+    fn or(&mut self) -> Result<bool, String> {
+        let mut result = self.and()?;
+        while self.peek_is("-o") {
+            self.pos += 1;
+            result |= self.and()?;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = self.not()?;
+        while self.peek_is("-a") {
+            self.pos += 1;
+            result &= self.not()?;
+        }
+        Ok(result)
+    }
+
+    fn not(&mut self) -> Result<bool, String> {
+        if self.peek_is("!") {
+            self.pos += 1;
+            return self.not().map(|b| !b);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<bool, String> {
+        let arg = self.next()?;
+        if arg == "(" {
+            let result = self.or()?;
+            if !self.peek_is(")") {
+                return Err("`)' expected".into());
+            }
+            self.pos += 1;
+            return Ok(result);
+        }
+        if let Some(&op) = self.args.get(self.pos).filter(|op| is_binary(op)) {
+            let lhs = arg;
+            self.pos += 1;
+            let rhs = self.next()?;
+            return binary(lhs, op, rhs);
+        }
+        if UNARY_OPS.contains(&arg) {
+            let operand = self.next()?;
+            return Ok(unary(arg, operand, self.ctx));
+        }
+        Ok(!arg.is_empty())
+    }
+}
+
+/// Evaluates a unary operator from `UNARY_OPS`.
+pub fn unary(op: &str, arg: &str, ctx: &CommandContext) -> bool {
+    let meta = || fs::metadata(arg).ok();
+    let mode_bit = |bit: u32| meta().is_some_and(|m| m.mode() & bit != 0);
+    match op {
+        "-z" => arg.is_empty(),
+        "-n" => !arg.is_empty(),
+        "-v" => ctx.get_var(arg).is_some(),
+        "-o" => ctx.options.get(arg) == Some(true),
+        "-a" | "-e" => meta().is_some(),
+        "-f" => meta().is_some_and(|m| m.is_file()),
+        "-d" => meta().is_some_and(|m| m.is_dir()),
+        "-b" => meta().is_some_and(|m| m.file_type().is_block_device()),
+        "-c" => meta().is_some_and(|m| m.file_type().is_char_device()),
+        "-p" => meta().is_some_and(|m| m.file_type().is_fifo()),
+        "-S" => meta().is_some_and(|m| m.file_type().is_socket()),
+        "-h" | "-L" => fs::symlink_metadata(arg).is_ok_and(|m| m.file_type().is_symlink()),
+        "-s" => meta().is_some_and(|m| m.len() > 0),
+        "-u" => mode_bit(0o4000),
+        "-g" => mode_bit(0o2000),
+        "-k" => mode_bit(0o1000),
+        "-r" => access(arg, libc::R_OK),
+        "-w" => access(arg, libc::W_OK),
+        "-x" => access(arg, libc::X_OK),
+        "-O" => meta().is_some_and(|m| m.uid() == unsafe { libc::geteuid() }),
+        "-G" => meta().is_some_and(|m| m.gid() == unsafe { libc::getegid() }),
+        "-N" => meta().is_some_and(|m| m.mtime() > m.atime()),
+        "-t" => arg
+            .trim()
+            .parse::<i32>()
+            .is_ok_and(|fd| unsafe { libc::isatty(fd) } == 1),
+        _ => false,
+    }
+}
+
+/// Evaluates a binary operator from `BINARY_OPS` other than `=~`.
+pub fn binary(lhs: &str, op: &str, rhs: &str) -> Result<bool, String> {
+    Ok(match op {
+        "=" | "==" => lhs == rhs,
+        "!=" => lhs != rhs,
+        "<" => lhs < rhs,
+        ">" => lhs > rhs,
+        "-nt" => match (mtime(lhs), mtime(rhs)) {
+            (Some(a), Some(b)) => a > b,
+            (a, _) => a.is_some(),
+        },
+        "-ot" => match (mtime(lhs), mtime(rhs)) {
+            (Some(a), Some(b)) => a < b,
+            (_, b) => b.is_some(),
+        },
+        "-ef" => match (fs::metadata(lhs), fs::metadata(rhs)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        },
+        _ => {
+            let (a, b) = (integer(lhs)?, integer(rhs)?);
+            match op {
+                "-eq" => a == b,
+                "-ne" => a != b,
+                "-lt" => a < b,
+                "-le" => a <= b,
+                "-gt" => a > b,
+                "-ge" => a >= b,
+                _ => return Err(format!("{}: binary operator expected", op)),
+            }
+        }
+    })
+}
+
+fn integer(s: &str) -> Result<i64, String> {
+    let t = s.trim();
+    t.strip_prefix('+')
+        .unwrap_or(t)
+        .parse()
+        .map_err(|_| format!("{}: integer expression expected", s))
+}
+
+fn mtime(path: &str) -> Option<std::time::SystemTime> {
+    fs::metadata(path).and_then(|m: Metadata| m.modified()).ok()
+}
+
+fn access(path: &str, mode: libc::c_int) -> bool {
+    match CString::new(path) {
+        Ok(p) => unsafe { libc::access(p.as_ptr(), mode) == 0 },
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators() {
+        assert_eq!(binary("10", "-gt", "9"), Ok(true));
+        assert_eq!(binary(" -3", "-lt", "+2"), Ok(true));
+        assert_eq!(
+            binary("x", "-eq", "1"),
+            Err("x: integer expression expected".to_string())
+        );
+        assert_eq!(binary("abc", "<", "abd"), Ok(true));
+        assert_eq!(binary("/", "-ef", "/."), Ok(true));
+        assert_eq!(binary("/", "-nt", "/nonexistent"), Ok(true));
+    }
+}
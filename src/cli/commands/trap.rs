@@ -0,0 +1,152 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::traps::Condition;
+use crate::utils::signal;
+
+pub struct TrapCommand;
+
+impl TrapCommand {
+    /// Prints the traps on `specs`, or every trap, as commands that set
+    /// them again.
+    fn print(&self, specs: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut status = 0;
+        let mut lines = vec![];
+        if specs.is_empty() {
+            lines.extend(
+                ctx.traps
+                    .iter()
+                    .map(|(cond, action)| set_command(cond, action)),
+            );
+        }
+        for spec in specs {
+            match Condition::parse(spec) {
+                Some(cond) => {
+                    let action = ctx.traps.iter().find(|(c, _)| *c == cond);
+                    lines.extend(action.map(|(cond, action)| set_command(cond, action)));
+                }
+                None => {
+                    invalid(spec, ctx);
+                    status = 1;
+                }
+            }
+        }
+        for line in lines {
+            ctx.write_stdout_line(&line)?;
+        }
+        Ok(status)
+    }
+}
+
+/// The `trap` command that sets `action` on `cond`.
+fn set_command(cond: Condition, action: &str) -> String {
+    format!("trap -- '{}' {}", action.replace('\'', "'\\''"), cond)
+}
+
+/// Whether `arg` is an unsigned number, which POSIX reads as a signal
+/// rather than an action.
+fn is_number(arg: &str) -> bool {
+    !arg.is_empty() && arg.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn invalid(spec: &str, ctx: &mut CommandContext) {
+    let msg = format!("trap: {}: invalid signal specification", spec);
+    ctx.stderr.write_line(&msg).ok();
+}
+
+impl Command for TrapCommand {
+    fn name(&self) -> &'static str {
+        "trap"
+    }
+
+    fn description(&self) -> &'static str {
+        "Trap signals and other events"
+    }
+
+    fn usage(&self) -> &'static str {
+        "trap [-lp] [[action] signal_spec ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Runs ACTION when the shell receives one of the signals SIGNAL_SPEC. A
+caught signal is handled once the command running when it arrives has
+finished. An ACTION of `-' resets each signal to its original action,
+and an empty ACTION ignores it, in the commands the shell runs as well.
+With a single SIGNAL_SPEC, or one that is a number, ACTION may be left
+out and the signals are reset.
+
+A SIGNAL_SPEC is a signal name, with or without SIG and in any case, or
+a number, or one of:
+  EXIT    the shell exiting; 0 is the same
+  DEBUG   before each simple command
+  ERR     a command failing where `set -e' would exit
+  RETURN  a function or sourced script returning; the shell has neither
+          yet, so this never runs
+
+Subshells start with no traps, but keep ignoring ignored signals.
+
+Options:
+  -l  list the signal names and numbers
+  -p  print the traps on each SIGNAL_SPEC, or every trap, as commands
+
+Exit Status:
+Returns 0 unless an invalid option or SIGNAL_SPEC is given."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut print = false;
+        let mut list = false;
+        let mut args = args;
+        while let Some(flags) = args
+            .first()
+            .filter(|a| a.len() > 1)
+            .and_then(|a| a.strip_prefix('-'))
+        {
+            args = &args[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'l' => list = true,
+                    'p' => print = true,
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+
+        if list {
+            for line in signal::listing() {
+                ctx.write_stdout_line(&line)?;
+            }
+            return Ok(0);
+        }
+        if print {
+            return self.print(args, ctx);
+        }
+        let (action, specs) = match args {
+            [] => return self.print(args, ctx),
+            [first, rest @ ..] if rest.is_empty() || is_number(first) => (None, args),
+            ["-", specs @ ..] => (None, specs),
+            [action, specs @ ..] => (Some(*action), specs),
+        };
+        let mut status = 0;
+        for spec in specs {
+            let Some(cond) = Condition::parse(spec) else {
+                invalid(spec, ctx);
+                status = 1;
+                continue;
+            };
+            match action {
+                Some(action) => ctx.traps.set(cond, action.to_string()),
+                None => ctx.traps.reset(cond),
+            }
+        }
+        Ok(status)
+    }
+}
@@ -1,9 +1,71 @@
 use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::parser::parse_list::KEYWORDS;
 use crate::cli::registry::CommandRegistry;
-use crate::utils::path_lookup::find_in_path;
+use crate::utils::path_lookup::find_all_in_path;
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Weak;
 
+/// One thing a command name can refer to, in the order the shell tries them.
+pub enum Resolution {
+    Keyword,
+    Builtin,
+    File(PathBuf),
+}
+
+impl Resolution {
+    /// The word `type -t` prints.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Resolution::Keyword => "keyword",
+            Resolution::Builtin => "builtin",
+            Resolution::File(_) => "file",
+        }
+    }
+
+    /// The sentence `type` prints.
+    pub fn describe(&self, name: &str) -> String {
+        match self {
+            Resolution::Keyword => format!("{} is a shell keyword", name),
+            Resolution::Builtin => format!("{} is a shell builtin", name),
+            Resolution::File(path) => format!("{} is {}", name, path.display()),
+        }
+    }
+}
+
+/// What `name` resolves to: only the first match unless `all` is set. With
+/// `path_only`, keywords and builtins are skipped.
+pub fn resolve(
+    name: &str,
+    registry: &CommandRegistry,
+    ctx: &CommandContext,
+    all: bool,
+    path_only: bool,
+) -> Vec<Resolution> {
+    let mut found = vec![];
+    if !path_only {
+        if KEYWORDS.contains(&name) {
+            found.push(Resolution::Keyword);
+        }
+        if registry.get(name).is_some_and(|cmd| cmd.is_builtin()) {
+            found.push(Resolution::Builtin);
+        }
+    }
+    if all {
+        let paths = find_all_in_path(name, &ctx.path_var());
+        found.extend(paths.into_iter().map(Resolution::File));
+    } else if found.is_empty() {
+        if let Some(path) = ctx.hash.peek(name, &ctx.path_var()) {
+            found.push(Resolution::File(path));
+        }
+    }
+    if !all {
+        found.truncate(1);
+    }
+    found
+}
+
 pub struct TypeCommand {
     pub registry: Weak<RefCell<CommandRegistry>>,
 }
@@ -17,34 +79,83 @@ impl Command for TypeCommand {
         "Show if command is built-in"
     }
 
-    fn execute(&self, args: &[&str], ctx: &mut CommandContext) {
-        let cmd_name = match args.get(0) {
-            Some(cmd_str) => cmd_str,
-            None => {
+    fn usage(&self) -> &'static str {
+        "type [-aptP] name [name ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+For each NAME, tells how it would be interpreted if used as a command
+name.
+
+Options:
+  -a  show every place NAME is found, not just the first
+  -p  print the path that would run, or nothing if NAME is not a file
+  -P  search PATH for NAME even if it is a builtin or keyword
+  -t  print a single word: keyword, builtin or file
+
+Exit Status:
+Returns 0 if every NAME is found, 1 otherwise."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let (mut all, mut kind_only, mut path, mut force_path) = (false, false, false, false);
+        let mut names = args;
+        while let Some(flags) = names.first().and_then(|a| a.strip_prefix('-')) {
+            names = &names[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'a' => all = true,
+                    't' => kind_only = true,
+                    'p' => path = true,
+                    'P' => force_path = true,
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+        if names.is_empty() {
+            if args.is_empty() {
                 ctx.stderr.write_line("Usage: type <command-name>").ok();
-                return;
+                return Ok(1);
             }
+            return Ok(0);
+        }
+        let Some(reg) = self.registry.upgrade() else {
+            return Ok(0);
         };
-        if let Some(reg) = self.registry.upgrade() {
-            let reg = reg.borrow();
-            if let Some(cmd) = reg.get(cmd_name) {
-                if cmd.is_builtin() {
-                    ctx.stdout
-                        .write_line(format!("{} is a shell builtin", cmd_name).as_str())
+        let reg = reg.borrow();
+
+        let mut status = 0;
+        for name in names {
+            let found = resolve(name, &reg, ctx, all, force_path);
+            if found.is_empty() {
+                if !kind_only && !path && !force_path {
+                    ctx.stderr
+                        .write_line(format!("{}: not found", name).as_str())
                         .ok();
-                    return;
                 }
+                status = 1;
+                continue;
             }
-
-            if let Some(path) = find_in_path(cmd_name) {
-                ctx.stdout
-                    .write_line(format!("{} is {}", cmd_name, path.display()).as_str())
-                    .ok();
-                return;
+            for res in &found {
+                let line = match res {
+                    _ if kind_only => res.kind().to_string(),
+                    Resolution::File(p) if path || force_path => p.display().to_string(),
+                    // -p prints nothing for names that are not files
+                    _ if path => continue,
+                    _ => res.describe(name),
+                };
+                ctx.write_stdout_line(&line)?;
             }
         }
-        ctx.stderr
-            .write_line(format!("{}: not found", cmd_name).as_str())
-            .ok();
+        Ok(status)
     }
 }
@@ -0,0 +1,83 @@
+use crate::cli::command::{Command, CommandContext};
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::vars::is_name;
+
+pub struct UnsetCommand;
+
+impl Command for UnsetCommand {
+    fn name(&self) -> &'static str {
+        "unset"
+    }
+
+    fn description(&self) -> &'static str {
+        "Unset values and attributes of shell variables"
+    }
+
+    fn usage(&self) -> &'static str {
+        "unset [-nv] [name ...]"
+    }
+
+    fn help(&self) -> &'static str {
+        "\
+Removes each NAME along with its attributes. NAME[SUBSCRIPT] removes one
+element of an array, and NAME[@] the whole array. Readonly variables
+cannot be unset.
+
+Options:
+  -n  unset each NAME itself, not the variable it refers to
+  -v  treat each NAME as a variable, which is the default
+
+Exit Status:
+Returns 0 unless an invalid option is given or a NAME is readonly or not
+valid."
+    }
+
+    fn execute(&self, args: &[&str], ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        let mut nameref = false;
+        let mut names = args;
+        while let Some(flags) = names.first().and_then(|a| a.strip_prefix('-')) {
+            names = &names[1..];
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'n' => nameref = true,
+                    'v' => {}
+                    _ => {
+                        return Err(ShellError::usage(
+                            self,
+                            format!("-{}: invalid option", flag),
+                        ))
+                    }
+                }
+            }
+        }
+
+        let mut status = 0;
+        for arg in names {
+            let (name, subscript) = match arg.strip_suffix(']').and_then(|a| a.split_once('[')) {
+                Some((name, sub)) => (name, Some(sub)),
+                None => (*arg, None),
+            };
+            if !is_name(name) {
+                let msg = format!("unset: `{}': not a valid identifier", arg);
+                ctx.stderr.write_line(&msg).ok();
+                status = 1;
+                continue;
+            }
+            if nameref && subscript.is_none() {
+                ctx.vars.clear_nameref(name);
+            }
+            let result = match subscript {
+                Some("@" | "*") | None => ctx.vars.unset(name),
+                Some(sub) => ctx.vars.unset_element(name, sub),
+            };
+            if let Err(e) = result {
+                ctx.stderr.write_line(&format!("unset: {}", e)).ok();
+                status = 1;
+            }
+        }
+        Ok(status)
+    }
+}
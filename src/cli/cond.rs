@@ -0,0 +1,107 @@
+use crate::cli::command::CommandContext;
+use crate::cli::commands::test;
+use crate::cli::expand::{expand_pattern, expand_plain};
+use crate::cli::parser::parse_cond::CondExpr;
+use crate::cli::parser::word::{Word, WordPart};
+use crate::cli::registry::CommandRegistry;
+use crate::utils::glob;
+use crate::utils::regex::Regex;
+
+/// Evaluates a `[[ ]]` expression. Operands are expanded without field
+/// splitting or pathname expansion, and only as far as `&&` and `||` need.
+pub fn evaluate(
+    expr: &CondExpr,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<bool, String> {
+    match expr {
+        CondExpr::Word(word) => Ok(!expand_plain(word, ctx, registry)?.is_empty()),
+        CondExpr::Unary(op, word) => {
+            let arg = expand_plain(word, ctx, registry)?;
+            Ok(test::unary(op, &arg, ctx))
+        }
+        CondExpr::Binary(lhs, op, rhs) => {
+            let lhs = expand_plain(lhs, ctx, registry)?;
+            match op.as_str() {
+                "=" | "==" => Ok(glob::pattern_matches(
+                    &expand_pattern(rhs, ctx, registry)?,
+                    &lhs,
+                )),
+                "!=" => Ok(!glob::pattern_matches(
+                    &expand_pattern(rhs, ctx, registry)?,
+                    &lhs,
+                )),
+                "=~" => regex_match(&lhs, rhs, ctx, registry),
+                // integer operands are arithmetic expressions
+                "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge" => {
+                    let rhs = expand_plain(rhs, ctx, registry)?;
+                    let (a, b) = (integer(&lhs, ctx)?, integer(&rhs, ctx)?);
+                    test::binary(&a.to_string(), op, &b.to_string())
+                }
+                _ => test::binary(&lhs, op, &expand_plain(rhs, ctx, registry)?),
+            }
+        }
+        CondExpr::Not(e) => evaluate(e, ctx, registry).map(|b| !b),
+        CondExpr::And(a, b) => Ok(evaluate(a, ctx, registry)? && evaluate(b, ctx, registry)?),
+        CondExpr::Or(a, b) => Ok(evaluate(a, ctx, registry)? || evaluate(b, ctx, registry)?),
+        CondExpr::Group(e) => evaluate(e, ctx, registry),
+    }
+}
+
+fn integer(expr: &str, ctx: &CommandContext) -> Result<i64, String> {
+    ctx.vars.integer_value(expr).map_err(|e| e.to_string())
+}
+
+/// Matches `text` against the extended regular expression `rhs`, in which
+/// quoted parts match literally. The matched text, followed by that of
+/// each group, is stored in the array `BASH_REMATCH`.
+fn regex_match(
+    text: &str,
+    rhs: &Word,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<bool, String> {
+    let mut pattern = String::new();
+    for part in &rhs.parts {
+        let quoted = matches!(
+            part,
+            WordPart::Quoted(_)
+                | WordPart::Param { quoted: true, .. }
+                | WordPart::CommandSub { quoted: true, .. }
+        );
+        let word = Word {
+            parts: vec![part.clone()],
+        };
+        let value = expand_plain(&word, ctx, registry)?;
+        if quoted {
+            pattern.push_str(&regex_escape(&value));
+        } else {
+            pattern.push_str(&value);
+        }
+    }
+    let re = Regex::new(&pattern).map_err(|e| format!("{}: {}", pattern, e))?;
+    match re.captures(text) {
+        Some(groups) => {
+            let groups = groups.into_iter().map(|g| (None, g)).collect();
+            ctx.vars
+                .assign_array("BASH_REMATCH", groups, false)
+                .map_err(|e| e.to_string())?;
+            Ok(true)
+        }
+        None => {
+            ctx.vars.unset("BASH_REMATCH").map_err(|e| e.to_string())?;
+            Ok(false)
+        }
+    }
+}
+
+fn regex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
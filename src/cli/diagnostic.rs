@@ -0,0 +1,69 @@
+use crate::cli::parser::error::Span;
+
+/// Where a piece of source text came from: a script name and the line
+/// number its first line had in that script.
+#[derive(Debug, Clone, Copy)]
+pub struct Origin<'a> {
+    pub name: &'a str,
+    pub line: usize,
+}
+
+/// Formats `message` for the error at `span` in `source`, followed by the
+/// offending line and a caret under the span.
+///
+/// Errors from scripts are prefixed with `name:line:`, everything else with
+/// `shell:`.
+pub fn render(source: &str, span: Span, message: &str, origin: Option<Origin>) -> String {
+    // an error at the very end points at the last line, not the empty one
+    // after its newline
+    let mut at = span.start.min(source.len());
+    if at == source.len() && source[..at].ends_with('\n') {
+        at -= 1;
+    }
+    let line_start = source[..at].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[at..].find('\n').map_or(source.len(), |i| at + i);
+    let line = &source[line_start..line_end];
+
+    let prefix = match origin {
+        Some(o) => format!(
+            "{}:{}:",
+            o.name,
+            o.line + source[..at].matches('\n').count()
+        ),
+        None => "shell:".to_string(),
+    };
+    let column = source[line_start..at].chars().count();
+    let width = source[at..span.end.clamp(at, line_end)]
+        .chars()
+        .count()
+        .max(1);
+    format!(
+        "{} {}\n  {}\n  {}{}",
+        prefix,
+        message,
+        line,
+        " ".repeat(column),
+        "^".repeat(width)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caret_under_span() {
+        let text = render("echo a; )\n", Span::new(8, 9), "oops", None);
+        assert_eq!(text, "shell: oops\n  echo a; )\n          ^");
+    }
+
+    #[test]
+    fn script_line_numbers() {
+        let origin = Origin {
+            name: "build.sh",
+            line: 4,
+        };
+        let text = render("{ echo a\necho b\n", Span::new(16, 16), "eof", Some(origin));
+        assert_eq!(text, "build.sh:5: eof\n  echo b\n        ^");
+    }
+}
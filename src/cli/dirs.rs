@@ -0,0 +1,74 @@
+/// The directory stack used by `pushd`, `popd` and `dirs`. The current
+/// directory is always entry 0 and is not stored here; `dirs` holds the
+/// entries below it.
+#[derive(Debug, Clone, Default)]
+pub struct DirStack {
+    dirs: Vec<String>,
+}
+
+impl DirStack {
+    /// Every entry, starting with the current directory `pwd`.
+    pub fn entries(&self, pwd: &str) -> Vec<String> {
+        let mut all = vec![pwd.to_string()];
+        all.extend(self.dirs.iter().cloned());
+        all
+    }
+
+    /// Replaces the stored entries with `entries` minus the first, which
+    /// becomes the current directory.
+    pub fn set_entries(&mut self, mut entries: Vec<String>) {
+        entries.remove(0);
+        self.dirs = entries;
+    }
+
+    /// The number of entries, counting the current directory, so never zero.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.dirs.len() + 1
+    }
+
+    pub fn push(&mut self, dir: String) {
+        self.dirs.insert(0, dir);
+    }
+
+    pub fn clear(&mut self) {
+        self.dirs.clear();
+    }
+
+    /// The entry `+N` (counting from the top) or `-N` (counting from the
+    /// bottom) refers to, given with its sign.
+    pub fn index(&self, spec: &str) -> Option<usize> {
+        let (from_bottom, digits) = match spec.as_bytes().first()? {
+            b'+' => (false, &spec[1..]),
+            b'-' => (true, &spec[1..]),
+            _ => (false, spec),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let n: usize = digits.parse().ok()?;
+        if n >= self.len() {
+            return None;
+        }
+        Some(if from_bottom { self.len() - 1 - n } else { n })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexing() {
+        let mut stack = DirStack::default();
+        stack.push("/b".into());
+        stack.push("/a".into());
+        assert_eq!(stack.entries("/cwd"), vec!["/cwd", "/a", "/b"]);
+        assert_eq!(stack.index("+0"), Some(0));
+        assert_eq!(stack.index("2"), Some(2));
+        assert_eq!(stack.index("-0"), Some(2));
+        assert_eq!(stack.index("-2"), Some(0));
+        assert_eq!(stack.index("+3"), None);
+        assert_eq!(stack.index("+x"), None);
+    }
+}
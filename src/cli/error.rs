@@ -0,0 +1,73 @@
+use crate::cli::command::Command;
+use crate::utils::sys;
+use std::io;
+use thiserror::Error;
+
+/// A command's exit status: 0 for success, non-zero for failure.
+pub type ExitStatus = i32;
+
+/// A change in the flow of control that unwinds through the commands
+/// enclosing the one that asked for it, until something handles it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Leave the shell (or subshell) with the given status.
+    Exit(ExitStatus),
+    /// Leave the current function or sourced script.
+    Return(ExitStatus),
+    /// Leave the given number of enclosing loops.
+    Break(usize),
+    /// Start the next iteration of the given enclosing loop.
+    Continue(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum ShellError {
+    /// Not a failure as such; see `Control`.
+    #[error("unhandled {0:?}")]
+    Control(Control),
+    /// A failure reported as `name: message`, ending the command with
+    /// `status`.
+    #[error("{message}")]
+    Failed { status: ExitStatus, message: String },
+    /// Output could not be written, as when the reader of a pipe has gone.
+    #[error("write error: {}", sys::error_message(.0))]
+    Write(io::Error),
+}
+
+impl ShellError {
+    pub fn failed(status: ExitStatus, message: impl Into<String>) -> Self {
+        ShellError::Failed {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// An invalid option or operand, reported along with the usage of
+    /// `cmd`. Ends the command with status 2.
+    pub fn usage(cmd: &dyn Command, message: impl Into<String>) -> Self {
+        let message = format!("{}\n{}: usage: {}", message.into(), cmd.name(), cmd.usage());
+        Self::failed(2, message)
+    }
+
+    pub fn exit(status: ExitStatus) -> Self {
+        ShellError::Control(Control::Exit(status))
+    }
+
+    /// The status the shell is left with when this error ends a command.
+    pub fn status(&self) -> ExitStatus {
+        match self {
+            ShellError::Control(Control::Exit(status) | Control::Return(status)) => *status,
+            ShellError::Control(Control::Break(_) | Control::Continue(_)) => 0,
+            ShellError::Failed { status, .. } => *status,
+            ShellError::Write(_) => 1,
+        }
+    }
+}
+
+/// The status a forked child should exit with after running something.
+pub fn final_status(result: Result<ExitStatus, ShellError>) -> ExitStatus {
+    match result {
+        Ok(status) => status,
+        Err(e) => e.status(),
+    }
+}
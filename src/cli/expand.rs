@@ -0,0 +1,723 @@
+use crate::cli::command::CommandContext;
+use crate::cli::parser::parse_param::{parse_param, DefaultKind, ParamOp, ReplaceMode};
+use crate::cli::parser::tokenize::ArgvTokenizer;
+use crate::cli::parser::word::{Word, WordPart};
+use crate::cli::registry::CommandRegistry;
+use crate::cli::vars::is_name;
+use crate::utils::{glob, sys};
+use std::ops::Range;
+
+const DEFAULT_IFS: &str = " \t\n";
+
+/// A field being assembled during expansion. `pattern` mirrors `value` with
+/// quoted characters escaped, for pathname expansion.
+#[derive(Default)]
+struct Field {
+    value: String,
+    pattern: String,
+    globbable: bool,
+    /// Set once quoted text or a field delimiter guarantees the field exists
+    /// even when empty.
+    forced: bool,
+}
+
+impl Field {
+    fn push_unquoted(&mut self, s: &str) {
+        self.value.push_str(s);
+        self.pattern.push_str(s);
+        self.globbable |= glob::has_glob_chars(s);
+    }
+
+    fn push_quoted(&mut self, s: &str) {
+        self.value.push_str(s);
+        self.pattern.push_str(&glob::escape(s));
+        self.forced = true;
+    }
+
+    fn is_present(&self) -> bool {
+        self.forced || !self.value.is_empty()
+    }
+}
+
+/// Performs tilde expansion, parameter expansion, command substitution,
+/// field splitting and pathname expansion on `words`, in that order.
+pub fn expand_words(
+    words: &[Word],
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<Vec<String>, String> {
+    let mut out = vec![];
+    for word in words {
+        let fields = expand_fields(word, true, ctx, registry)?;
+        for field in fields {
+            if field.globbable && !ctx.options.noglob {
+                let matches = glob::expand(&field.pattern);
+                if !matches.is_empty() {
+                    out.extend(matches);
+                    continue;
+                }
+            }
+            out.push(field.value);
+        }
+    }
+    Ok(out)
+}
+
+/// Expands a word that must produce exactly one field, such as a
+/// redirection target.
+pub fn expand_single(
+    word: &Word,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<String, String> {
+    let mut fields = expand_words(std::slice::from_ref(word), ctx, registry)?;
+    if fields.len() != 1 {
+        return Err(format!("{}: ambiguous redirect", word));
+    }
+    Ok(fields.remove(0))
+}
+
+/// Expands `word` without field splitting or pathname expansion, as the
+/// operands of `[[ ]]` and the values of assignments are.
+pub fn expand_plain(
+    word: &Word,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<String, String> {
+    let fields = expand_fields(word, false, ctx, registry)?;
+    Ok(fields.into_iter().map(|f| f.value).collect())
+}
+
+/// Like `expand_plain`, but with quoted characters escaped so that the
+/// result, used as a pattern, matches them literally.
+pub fn expand_pattern(
+    word: &Word,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<String, String> {
+    let fields = expand_fields(word, false, ctx, registry)?;
+    Ok(fields.into_iter().map(|f| f.pattern).collect())
+}
+
+fn expand_fields(
+    word: &Word,
+    split: bool,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<Vec<Field>, String> {
+    let mut out = Fields {
+        ifs: ctx.get_var("IFS").unwrap_or_else(|| DEFAULT_IFS.into()),
+        split,
+        done: vec![],
+        cur: Field::default(),
+    };
+    expand_parts(&word.parts, false, &mut out, ctx, registry)?;
+    if out.cur.is_present() {
+        out.done.push(out.cur);
+    }
+    Ok(out.done)
+}
+
+/// The fields of a word expanded so far.
+struct Fields {
+    ifs: String,
+    split: bool,
+    done: Vec<Field>,
+    cur: Field,
+}
+
+impl Fields {
+    /// Adds the result of an expansion, split into fields unless quoted.
+    fn push_expansion(&mut self, value: &str, quoted: bool) {
+        if quoted {
+            self.cur.push_quoted(value);
+        } else if self.split {
+            split_into(value, &self.ifs, &mut self.cur, &mut self.done);
+        } else {
+            self.cur.push_unquoted(value);
+        }
+    }
+
+    /// Adds the values of `$@`, `$*` or an array, each making a separate
+    /// field. They are joined instead in "$*" (`star`), with the first
+    /// character of IFS, and wherever fields are not split.
+    fn push_list(&mut self, values: &[String], star: bool, quoted: bool) {
+        if (star && quoted) || !self.split {
+            let sep = match star {
+                true => self
+                    .ifs
+                    .chars()
+                    .next()
+                    .map(String::from)
+                    .unwrap_or_default(),
+                false => " ".into(),
+            };
+            self.push_expansion(&values.join(&sep), quoted);
+            return;
+        }
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 && self.cur.is_present() {
+                self.done.push(std::mem::take(&mut self.cur));
+            }
+            self.push_expansion(value, quoted);
+        }
+    }
+}
+
+/// What a parameter expansion refers to: a single value, which may be
+/// unset, or the list from `$@`, `$*`, `${name[@]}` or `${name[*]}`.
+enum Target {
+    Single(Option<String>),
+    List { values: Vec<String>, star: bool },
+}
+
+/// Expands `parts` onto `out`. In the `operand` of an unquoted `${...}`
+/// expansion, literal text is split as the result of an expansion is.
+fn expand_parts(
+    parts: &[WordPart],
+    operand: bool,
+    out: &mut Fields,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<(), String> {
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            WordPart::Literal(s) if i == 0 && s.starts_with('~') => {
+                let (prefix, rest) = s.split_at(s.find('/').unwrap_or(s.len()));
+                match expand_tilde(&prefix[1..], ctx) {
+                    Some(home) => {
+                        out.cur.push_quoted(&home);
+                        out.cur.push_unquoted(rest);
+                    }
+                    None => out.cur.push_unquoted(s),
+                }
+            }
+            WordPart::Literal(s) if operand => out.push_expansion(s, false),
+            WordPart::Literal(s) => out.cur.push_unquoted(s),
+            WordPart::Quoted(s) => out.cur.push_quoted(s),
+            WordPart::Param { name, quoted } => {
+                expand_param(name, *quoted, out, ctx, registry)?;
+            }
+            WordPart::CommandSub { command, quoted } => {
+                let output = registry.capture_output(command, ctx);
+                out.push_expansion(&output, *quoted);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Expands `$name` or `${text}`.
+fn expand_param(
+    text: &str,
+    quoted: bool,
+    out: &mut Fields,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<(), String> {
+    let bad = || format!("${{{}}}: bad substitution", text);
+    let mut expr = parse_param(text).ok_or_else(bad)?;
+    let all = matches!(expr.subscript.as_deref(), Some("@" | "*"));
+    // ${!ref} is the name a nameref refers to
+    if let Some(var) = ctx.vars.lookup(&expr.name).filter(|v| v.nameref) {
+        if expr.indirect && expr.subscript.is_none() && expr.op == ParamOp::Plain {
+            let target = var
+                .value
+                .as_ref()
+                .and_then(|v| v.first())
+                .unwrap_or_default();
+            out.push_expansion(target, quoted);
+            return Ok(());
+        }
+    }
+    if expr.indirect && !all {
+        let target = ctx.get_var(&expr.name).unwrap_or_default();
+        if target.is_empty() {
+            return Err(format!("{}: invalid indirect expansion", expr.name));
+        }
+        match parse_param(&target) {
+            Some(t) if t.op == ParamOp::Plain && !t.indirect => {
+                expr.name = t.name;
+                expr.subscript = t.subscript;
+            }
+            _ => return Err(format!("{}: invalid variable name", target)),
+        }
+    }
+    let name = expr.name;
+    let subscript = match expr.subscript {
+        Some(sub) if sub == "@" || sub == "*" => Some(sub),
+        Some(sub) => {
+            let word = ArgvTokenizer::operand(&sub, true).map_err(|e| e.to_string())?;
+            Some(expand_plain(&word, ctx, registry)?)
+        }
+        None => None,
+    };
+    let shown = match &subscript {
+        Some(sub) => format!("{}[{}]", name, sub),
+        None => name.clone(),
+    };
+
+    let target = match subscript.as_deref() {
+        // ${!name[@]}: the indices or keys
+        Some(sub @ ("@" | "*")) if expr.indirect => Target::List {
+            values: ctx.vars.value(&name).map(|v| v.keys()).unwrap_or_default(),
+            star: sub == "*",
+        },
+        Some(sub @ ("@" | "*")) => Target::List {
+            values: match ctx.vars.value(&name) {
+                Some(value) => value.elements().into_iter().map(String::from).collect(),
+                None => vec![],
+            },
+            star: sub == "*",
+        },
+        Some(sub) => Target::Single(
+            ctx.vars
+                .get_element(&name, sub)
+                .map_err(|e| e.to_string())?
+                .map(str::to_string),
+        ),
+        None if name == "@" || name == "*" => Target::List {
+            values: ctx.params.positional.clone(),
+            star: name == "*",
+        },
+        None => Target::Single(ctx.get_var(&name)),
+    };
+
+    if let ParamOp::Default { kind, colon, word } = &expr.op {
+        let missing = match &target {
+            Target::Single(value) => value.as_ref().map_or(true, |v| *colon && v.is_empty()),
+            Target::List { values, .. } => {
+                values.is_empty() || (*colon && values.iter().all(String::is_empty))
+            }
+        };
+        let word = ArgvTokenizer::operand(word, quoted).map_err(|e| e.to_string())?;
+        match kind {
+            DefaultKind::Use if missing => expand_parts(&word.parts, !quoted, out, ctx, registry)?,
+            DefaultKind::Alternate if !missing => {
+                expand_parts(&word.parts, !quoted, out, ctx, registry)?
+            }
+            DefaultKind::Alternate => {}
+            DefaultKind::Assign if missing => {
+                if !is_name(&name) || all {
+                    return Err(format!("${}: cannot assign in this way", shown));
+                }
+                let value = expand_plain(&word, ctx, registry)?;
+                ctx.vars
+                    .assign(&name, subscript.as_deref(), value, false)
+                    .map_err(|e| e.to_string())?;
+                let value = ctx
+                    .vars
+                    .get_element(&name, subscript.as_deref().unwrap_or("0"));
+                let value = value
+                    .map_err(|e| e.to_string())?
+                    .unwrap_or_default()
+                    .to_string();
+                out.push_expansion(&value, quoted);
+            }
+            DefaultKind::Error if missing => {
+                let msg = expand_plain(&word, ctx, registry)?;
+                let set = matches!(target, Target::Single(Some(_)));
+                return Err(match msg.as_str() {
+                    "" if *colon => format!("{}: parameter null or not set", shown),
+                    "" if set => format!("{}: parameter null", shown),
+                    "" => format!("{}: parameter not set", shown),
+                    _ => format!("{}: {}", shown, msg),
+                });
+            }
+            _ => match target {
+                Target::Single(value) => out.push_expansion(&value.unwrap_or_default(), quoted),
+                Target::List { values, star } => out.push_list(&values, star, quoted),
+            },
+        }
+        return Ok(());
+    }
+
+    let (values, star) = match target {
+        Target::Single(None) if ctx.options.nounset => {
+            return Err(format!("{}: unbound variable", shown));
+        }
+        Target::Single(value) => (vec![value.unwrap_or_default()], None),
+        Target::List { values, star } => (values, Some(star)),
+    };
+    let values = match &expr.op {
+        ParamOp::Length => {
+            let len = match star {
+                Some(_) => values.len(),
+                None => values[0].chars().count(),
+            };
+            out.push_expansion(&len.to_string(), quoted);
+            return Ok(());
+        }
+        ParamOp::Substring { offset, length } => {
+            let offset = arithmetic(offset, ctx, registry)?;
+            let length = match length {
+                Some(length) => Some(arithmetic(length, ctx, registry)?),
+                None => None,
+            };
+            let negative = || format!("{}: substring expression < 0", length.unwrap_or_default());
+            match star {
+                None => {
+                    let chars: Vec<char> = values[0].chars().collect();
+                    let range = slice(chars.len(), offset, length).ok_or_else(negative)?;
+                    vec![chars[range].iter().collect()]
+                }
+                Some(_) if length.is_some_and(|n| n < 0) => return Err(negative()),
+                Some(_) => {
+                    // $0 comes before the positional parameters
+                    let mut values = values;
+                    if subscript.is_none() {
+                        values.insert(0, ctx.params.arg0.clone());
+                    }
+                    let range = slice(values.len(), offset, length).ok_or_else(negative)?;
+                    values[range].to_vec()
+                }
+            }
+        }
+        op => transform(op, values, quoted, ctx, registry)?,
+    };
+    match star {
+        Some(star) => out.push_list(&values, star, quoted),
+        None => out.push_expansion(&values[0], quoted),
+    }
+    Ok(())
+}
+
+/// Applies a pattern operator to each of `values`.
+fn transform(
+    op: &ParamOp,
+    values: Vec<String>,
+    quoted: bool,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<Vec<String>, String> {
+    let mut pattern = |word: &str| {
+        let word = ArgvTokenizer::operand(word, false).map_err(|e| e.to_string())?;
+        expand_pattern(&word, ctx, registry)
+    };
+    let result = match op {
+        ParamOp::RemovePrefix {
+            pattern: pat,
+            longest,
+        } => {
+            let pat = pattern(pat)?;
+            values
+                .into_iter()
+                .map(|value| {
+                    let mut ends: Vec<usize> = boundaries(&value).collect();
+                    if *longest {
+                        ends.reverse();
+                    }
+                    match ends
+                        .into_iter()
+                        .find(|&end| glob::pattern_matches(&pat, &value[..end]))
+                    {
+                        Some(end) => value[end..].to_string(),
+                        None => value,
+                    }
+                })
+                .collect()
+        }
+        ParamOp::RemoveSuffix {
+            pattern: pat,
+            longest,
+        } => {
+            let pat = pattern(pat)?;
+            values
+                .into_iter()
+                .map(|value| {
+                    let mut starts: Vec<usize> = boundaries(&value).collect();
+                    if !*longest {
+                        starts.reverse();
+                    }
+                    match starts
+                        .into_iter()
+                        .find(|&start| glob::pattern_matches(&pat, &value[start..]))
+                    {
+                        Some(start) => value[..start].to_string(),
+                        None => value,
+                    }
+                })
+                .collect()
+        }
+        ParamOp::Replace {
+            mode,
+            pattern: pat,
+            replacement,
+        } => {
+            let pat = pattern(pat)?;
+            let word = ArgvTokenizer::operand(replacement, quoted).map_err(|e| e.to_string())?;
+            let replacement = expand_plain(&word, ctx, registry)?;
+            values
+                .iter()
+                .map(|value| replace(value, &pat, &replacement, *mode))
+                .collect()
+        }
+        ParamOp::Case {
+            upper,
+            all,
+            pattern: pat,
+        } => {
+            let pat = match pat.as_str() {
+                "" => "?".to_string(),
+                _ => pattern(pat)?,
+            };
+            values
+                .iter()
+                .map(|value| change_case(value, &pat, *upper, *all))
+                .collect()
+        }
+        _ => values,
+    };
+    Ok(result)
+}
+
+/// Changes the case of the first character of `value` matching `pattern`,
+/// or of every one with `all`.
+fn change_case(value: &str, pattern: &str, upper: bool, all: bool) -> String {
+    let mut changed = false;
+    value
+        .chars()
+        .map(|c| {
+            if (changed && !all) || !glob::pattern_matches(pattern, &c.to_string()) {
+                return c.to_string();
+            }
+            changed = true;
+            if upper {
+                c.to_uppercase().to_string()
+            } else {
+                c.to_lowercase().to_string()
+            }
+        })
+        .collect()
+}
+
+/// The byte offsets of the character boundaries in `s`, from 0 to its
+/// length.
+fn boundaries(s: &str) -> impl DoubleEndedIterator<Item = usize> + '_ {
+    s.char_indices().map(|(i, _)| i).chain([s.len()])
+}
+
+/// Replaces the longest match of `pattern` in `value`, or every match for
+/// `${var//...}`. `/#` and `/%` anchor the match to the start or the end.
+fn replace(value: &str, pattern: &str, replacement: &str, mode: ReplaceMode) -> String {
+    let matches = |start: usize, end: usize| glob::pattern_matches(pattern, &value[start..end]);
+    match mode {
+        ReplaceMode::Prefix => match boundaries(value).rev().find(|&end| matches(0, end)) {
+            Some(end) => format!("{}{}", replacement, &value[end..]),
+            None => value.to_string(),
+        },
+        ReplaceMode::Suffix => match boundaries(value).find(|&start| matches(start, value.len())) {
+            Some(start) => format!("{}{}", &value[..start], replacement),
+            None => value.to_string(),
+        },
+        ReplaceMode::First | ReplaceMode::All => {
+            if pattern.is_empty() {
+                return value.to_string();
+            }
+            let mut out = String::new();
+            let mut pos = 0;
+            for start in boundaries(value) {
+                if start < pos {
+                    continue;
+                }
+                let longest = boundaries(value)
+                    .rev()
+                    .take_while(|&end| end > start)
+                    .find(|&end| matches(start, end));
+                let Some(end) = longest else {
+                    continue;
+                };
+                out.push_str(&value[pos..start]);
+                out.push_str(replacement);
+                pos = end;
+                if mode == ReplaceMode::First {
+                    break;
+                }
+            }
+            out.push_str(&value[pos..]);
+            out
+        }
+    }
+}
+
+/// The part of `len` characters or list items that `${var:offset:length}`
+/// selects. A negative offset counts back from the end, as does a negative
+/// length, which must not reach back past the offset.
+fn slice(len: usize, offset: i64, length: Option<i64>) -> Option<Range<usize>> {
+    let len = len as i64;
+    let start = if offset < 0 {
+        len.saturating_add(offset).max(0)
+    } else {
+        offset.min(len)
+    };
+    let end = match length {
+        None => len,
+        Some(n) if n < 0 => {
+            let end = len.saturating_add(n);
+            if end < start {
+                return None;
+            }
+            end
+        }
+        Some(n) => start.saturating_add(n).min(len),
+    };
+    Some(start as usize..end as usize)
+}
+
+/// Evaluates the offset or length of a substring, which is an arithmetic
+/// expression. A plain number too big for 64 bits saturates, as the
+/// bounds of the substring do.
+fn arithmetic(
+    text: &str,
+    ctx: &mut CommandContext,
+    registry: &CommandRegistry,
+) -> Result<i64, String> {
+    let word = ArgvTokenizer::operand(text, true).map_err(|e| e.to_string())?;
+    let text = expand_plain(&word, ctx, registry)?;
+    let number = text.trim();
+    let digits = number.strip_prefix('-').unwrap_or(number);
+    if digits.len() > 1 && !digits.starts_with('0') && digits.bytes().all(|b| b.is_ascii_digit()) {
+        if let Err(e) = number.parse::<i64>() {
+            let negative = *e.kind() == std::num::IntErrorKind::NegOverflow;
+            return Ok(if negative { i64::MIN } else { i64::MAX });
+        }
+    }
+    ctx.vars.integer_value(&text).map_err(|e| e.to_string())
+}
+
+/// Splits the result of an unquoted expansion on `ifs`, joining the first
+/// piece onto the field in progress and starting new fields at each
+/// delimiter.
+fn split_into(text: &str, ifs: &str, cur: &mut Field, fields: &mut Vec<Field>) {
+    let is_ifs = |c: char| ifs.contains(c);
+    let is_ifs_space = |c: char| is_ifs(c) && c.is_whitespace();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() && is_ifs_space(chars[i]) {
+        i += 1;
+    }
+    if i > 0 && cur.is_present() {
+        fields.push(std::mem::take(cur));
+    }
+
+    let mut first = true;
+    while i < chars.len() {
+        let mut piece = String::new();
+        while i < chars.len() && !is_ifs(chars[i]) {
+            piece.push(chars[i]);
+            i += 1;
+        }
+        if !first {
+            let mut field = std::mem::take(cur);
+            field.forced = true;
+            fields.push(field);
+        }
+        cur.push_unquoted(&piece);
+        first = false;
+        if i == chars.len() {
+            break;
+        }
+
+        while i < chars.len() && is_ifs_space(chars[i]) {
+            i += 1;
+        }
+        if i < chars.len() && is_ifs(chars[i]) {
+            i += 1;
+            while i < chars.len() && is_ifs_space(chars[i]) {
+                i += 1;
+            }
+        }
+        if i == chars.len() {
+            // A trailing delimiter ends the field without starting another.
+            cur.forced = true;
+            fields.push(std::mem::take(cur));
+        }
+    }
+}
+
+/// Expands `~` or `~user` (given without the tilde), along with `~+`
+/// (`PWD`), `~-` (`OLDPWD`) and `~N`, `~+N`, `~-N` (directory stack entries).
+fn expand_tilde(user: &str, ctx: &CommandContext) -> Option<String> {
+    match user {
+        "" => ctx.get_var("HOME"),
+        "+" => ctx.get_var("PWD"),
+        "-" => ctx.get_var("OLDPWD"),
+        _ if user.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-') => {
+            let pwd = ctx.get_var("PWD")?;
+            let i = ctx.dir_stack.index(user)?;
+            ctx.dir_stack.entries(&pwd).into_iter().nth(i)
+        }
+        _ => sys::home_dir_of(user),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::command::IoHandle;
+    use crate::cli::dirs::DirStack;
+    use crate::cli::hash::HashTable;
+    use crate::cli::jobs::JobTable;
+    use crate::cli::options::ShellOptions;
+    use crate::cli::params::Params;
+    use crate::cli::parser::parse_simple::word_of;
+    use crate::cli::parser::tokenize::ArgvTokenizer;
+    use crate::cli::traps::TrapTable;
+
+    fn expand(line: &str, vars: &[(&str, &str)]) -> Result<Vec<String>, String> {
+        let mut ctx = CommandContext {
+            stdin: IoHandle::Stdin,
+            stdout: IoHandle::Stdout,
+            stderr: IoHandle::Stderr,
+            vars: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            params: Params::default(),
+            last_status: 0,
+            jobs: JobTable::default(),
+            options: ShellOptions::default(),
+            interactive: false,
+            errexit_ignored: 0,
+            hash: HashTable::default(),
+            dir_stack: DirStack::default(),
+            traps: TrapTable::default(),
+        };
+        ctx.options.nounset = true;
+        let words: Vec<Word> = ArgvTokenizer::tokenize(line)
+            .unwrap()
+            .iter()
+            .filter_map(word_of)
+            .collect();
+        expand_words(&words, &mut ctx, &CommandRegistry::new())
+    }
+
+    #[test]
+    fn field_splitting() {
+        let vars = [("A", " x  y "), ("E", ""), ("C", "p:q::r")];
+        assert_eq!(expand("$A", &vars).unwrap(), vec!["x", "y"]);
+        assert_eq!(expand("a${A}b", &vars).unwrap(), vec!["a", "x", "y", "b"]);
+        assert_eq!(expand("\"$A\"", &vars).unwrap(), vec![" x  y "]);
+        assert_eq!(expand("$E \"$E\"", &vars).unwrap(), vec![""]);
+
+        let vars = [("IFS", ":"), ("C", "p:q::r:")];
+        assert_eq!(expand("$C", &vars).unwrap(), vec!["p", "q", "", "r"]);
+    }
+
+    #[test]
+    fn quoted_patterns_are_literal() {
+        assert_eq!(
+            expand("'/*' \\/*nonexistent*", &[]).unwrap(),
+            vec!["/*", "/*nonexistent*"]
+        );
+    }
+
+    #[test]
+    fn nounset() {
+        assert_eq!(
+            expand("$MISSING_VAR", &[]).unwrap_err(),
+            "MISSING_VAR: unbound variable"
+        );
+    }
+}
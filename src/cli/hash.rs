@@ -0,0 +1,119 @@
+use crate::utils::path_lookup::{find_in_path, is_executable};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct HashEntry {
+    pub path: PathBuf,
+    pub hits: u32,
+}
+
+/// Remembered locations of commands found through `PATH`, so each one is
+/// searched for only once. The table empties itself whenever `PATH` differs
+/// from the value it was filled under.
+#[derive(Debug, Clone, Default)]
+pub struct HashTable {
+    entries: BTreeMap<String, HashEntry>,
+    path_var: String,
+}
+
+impl HashTable {
+    /// Resolves `name` for execution, counting a hit. A remembered path that
+    /// is no longer executable is searched for again.
+    pub fn lookup(&mut self, name: &str, path_var: &str) -> Option<PathBuf> {
+        let path = self.find(name, path_var)?;
+        if let Some(entry) = self.entries.get_mut(name) {
+            entry.hits += 1;
+        }
+        Some(path)
+    }
+
+    /// Resolves `name` like [`HashTable::lookup`] without counting a hit,
+    /// for `hash name`.
+    pub fn find(&mut self, name: &str, path_var: &str) -> Option<PathBuf> {
+        if name.contains('/') {
+            return find_in_path(name, path_var);
+        }
+        if self.path_var != path_var {
+            self.entries.clear();
+            self.path_var = path_var.to_string();
+        }
+        if let Some(entry) = self.entries.get(name) {
+            if is_executable(&entry.path) {
+                return Some(entry.path.clone());
+            }
+            self.entries.remove(name);
+        }
+        let path = find_in_path(name, path_var)?;
+        self.insert(name, &path);
+        Some(path)
+    }
+
+    /// Resolves `name` using a remembered path if there is one, but records
+    /// nothing, for `type` and `command -v`.
+    pub fn peek(&self, name: &str, path_var: &str) -> Option<PathBuf> {
+        if !name.contains('/') && self.path_var == path_var {
+            let entry = self.entries.get(name).filter(|e| is_executable(&e.path));
+            if let Some(entry) = entry {
+                return Some(entry.path.clone());
+            }
+        }
+        find_in_path(name, path_var)
+    }
+
+    /// Remembers `path` for `name` without searching, as `hash -p` does.
+    pub fn insert(&mut self, name: &str, path: &Path) {
+        self.entries.insert(
+            name.to_string(),
+            HashEntry {
+                path: path.to_path_buf(),
+                hits: 0,
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HashEntry> {
+        self.entries.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &HashEntry)> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_and_invalidation() {
+        let mut table = HashTable::default();
+        assert_eq!(table.lookup("sh", "/bin"), Some(PathBuf::from("/bin/sh")));
+        table.lookup("sh", "/bin");
+        assert_eq!(table.get("sh").unwrap().hits, 2);
+
+        // peeking finds without remembering
+        assert_eq!(table.peek("cat", "/bin"), Some(PathBuf::from("/bin/cat")));
+        assert!(table.get("cat").is_none());
+
+        // a different PATH starts over
+        assert!(table.find("sh", "/usr/bin:/bin").is_some());
+        assert_eq!(table.get("sh").unwrap().hits, 0);
+
+        // a remembered path that disappeared is searched for again
+        table.insert("sh", Path::new("/nonexistent/sh"));
+        assert!(table.lookup("sh", "/usr/bin:/bin").is_some());
+        assert_ne!(
+            table.get("sh").unwrap().path,
+            PathBuf::from("/nonexistent/sh")
+        );
+    }
+}
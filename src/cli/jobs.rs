@@ -0,0 +1,85 @@
+use crate::utils::process::try_wait_pid;
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: usize,
+    /// The pid of the subshell running the job, which is also the ID of
+    /// the job's process group.
+    pub pid: libc::pid_t,
+    pub command: String,
+}
+
+/// Background jobs started with `&` that have not been reported as done.
+#[derive(Debug, Clone, Default)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+}
+
+impl JobTable {
+    pub fn add(&mut self, pid: libc::pid_t, command: String) -> &Job {
+        let id = self.jobs.iter().map(|j| j.id).max().unwrap_or(0) + 1;
+        self.jobs.push(Job { id, pid, command });
+        self.jobs.last().unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Finds the job named by a job spec without its `%`: a job number,
+    /// `%` or `+` for the current job, `-` for the previous one, the start
+    /// of a command, or `?` and a string the command contains.
+    pub fn find(&self, spec: &str) -> Result<&Job, String> {
+        let found = match spec {
+            "" | "%" | "+" => self.jobs.last(),
+            "-" => self.jobs.iter().rev().nth(1).or(self.jobs.last()),
+            _ if spec.bytes().all(|b| b.is_ascii_digit()) => {
+                self.jobs.iter().find(|j| spec.parse() == Ok(j.id))
+            }
+            _ => {
+                let matching: Vec<&Job> = match spec.strip_prefix('?') {
+                    Some(text) => self
+                        .jobs
+                        .iter()
+                        .filter(|j| j.command.contains(text))
+                        .collect(),
+                    None => self
+                        .jobs
+                        .iter()
+                        .filter(|j| j.command.starts_with(spec))
+                        .collect(),
+                };
+                if matching.len() > 1 {
+                    return Err(format!("%{}: ambiguous job spec", spec));
+                }
+                matching.first().copied()
+            }
+        };
+        found.ok_or_else(|| format!("%{}: no such job", spec))
+    }
+
+    /// Sends `SIGHUP` to the process group of every job still running, as
+    /// a shell with `huponexit` set does when it exits.
+    pub fn hangup(&self) {
+        for job in &self.jobs {
+            unsafe { libc::kill(-job.pid, libc::SIGHUP) };
+        }
+    }
+
+    /// Removes and returns the jobs that have finished, with their status.
+    pub fn reap(&mut self) -> Vec<(Job, i32)> {
+        let mut done = vec![];
+        self.jobs.retain(|job| match try_wait_pid(job.pid) {
+            Some(status) => {
+                done.push((job.clone(), status));
+                false
+            }
+            None => true,
+        });
+        done
+    }
+}
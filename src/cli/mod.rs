@@ -1,4 +1,17 @@
+pub mod arith;
 pub mod command;
 pub mod commands;
+pub mod cond;
+pub mod diagnostic;
+pub mod dirs;
+pub mod error;
+pub mod expand;
+pub mod hash;
+pub mod jobs;
+pub mod options;
+pub mod params;
 pub mod parser;
+pub mod prompt;
 pub mod registry;
+pub mod traps;
+pub mod vars;
@@ -0,0 +1,88 @@
+/// Shell options controlled by `set`.
+#[derive(Debug, Clone, Default)]
+pub struct ShellOptions {
+    pub errexit: bool,
+    pub huponexit: bool,
+    pub noclobber: bool,
+    pub noexec: bool,
+    pub noglob: bool,
+    pub nounset: bool,
+    pub physical: bool,
+    pub pipefail: bool,
+    pub xtrace: bool,
+}
+
+/// Long option names and their single-letter flags, in `set -o` order.
+pub const OPTIONS: &[(&str, Option<char>)] = &[
+    ("errexit", Some('e')),
+    ("huponexit", None),
+    ("noclobber", Some('C')),
+    ("noexec", Some('n')),
+    ("noglob", Some('f')),
+    ("nounset", Some('u')),
+    ("physical", Some('P')),
+    ("pipefail", None),
+    ("xtrace", Some('x')),
+];
+
+impl ShellOptions {
+    fn flag_mut(&mut self, name: &str) -> Option<&mut bool> {
+        Some(match name {
+            "errexit" => &mut self.errexit,
+            "huponexit" => &mut self.huponexit,
+            "noclobber" => &mut self.noclobber,
+            "noexec" => &mut self.noexec,
+            "noglob" => &mut self.noglob,
+            "nounset" => &mut self.nounset,
+            "physical" => &mut self.physical,
+            "pipefail" => &mut self.pipefail,
+            "xtrace" => &mut self.xtrace,
+            _ => return None,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        Some(match name {
+            "errexit" => self.errexit,
+            "huponexit" => self.huponexit,
+            "noclobber" => self.noclobber,
+            "noexec" => self.noexec,
+            "noglob" => self.noglob,
+            "nounset" => self.nounset,
+            "physical" => self.physical,
+            "pipefail" => self.pipefail,
+            "xtrace" => self.xtrace,
+            _ => return None,
+        })
+    }
+
+    /// Turns the named option on or off, returning `false` if no such
+    /// option exists.
+    pub fn set(&mut self, name: &str, on: bool) -> bool {
+        match self.flag_mut(name) {
+            Some(flag) => {
+                *flag = on;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn name_for_flag(flag: char) -> Option<&'static str> {
+        OPTIONS
+            .iter()
+            .find(|(_, f)| *f == Some(flag))
+            .map(|(name, _)| *name)
+    }
+
+    /// The single-letter flags currently enabled, as reported by `$-`.
+    pub fn flags(&self) -> String {
+        let mut flags: Vec<char> = OPTIONS
+            .iter()
+            .filter(|(name, _)| self.get(name) == Some(true))
+            .filter_map(|(_, flag)| *flag)
+            .collect();
+        flags.sort_by_key(|c| (c.is_uppercase(), *c));
+        flags.into_iter().collect()
+    }
+}
@@ -0,0 +1,44 @@
+/// The positional parameters and the special parameters the shell keeps
+/// for itself. `$?` and `$-` come from the rest of the context.
+#[derive(Debug, Clone)]
+pub struct Params {
+    /// `$0`: the shell, or the script it is running.
+    pub arg0: String,
+    /// `$1` onwards, set from a script's arguments or by `set --`.
+    pub positional: Vec<String>,
+    /// `$$`. Subshells keep their parent's.
+    pub shell_pid: u32,
+    /// `$!`: the last job started in the background.
+    pub last_background: Option<libc::pid_t>,
+    /// `$_`: the last argument of the previous command.
+    pub last_arg: String,
+}
+
+impl Params {
+    /// Positional parameter `n`, counting from 1; `$0` is separate.
+    pub fn nth(&self, n: usize) -> Option<&str> {
+        self.positional.get(n.checked_sub(1)?).map(String::as_str)
+    }
+
+    /// Drops the first `n` positional parameters, as `shift` does. Fails
+    /// without changing anything if there are fewer than `n`.
+    pub fn shift(&mut self, n: usize) -> bool {
+        if n > self.positional.len() {
+            return false;
+        }
+        self.positional.drain(..n);
+        true
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            arg0: String::new(),
+            positional: vec![],
+            shell_pid: std::process::id(),
+            last_background: None,
+            last_arg: String::new(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use thiserror::Error;
+
+/// A byte range in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TokenizeError {
+    #[error("unexpected end of file while looking for matching `{quote}'")]
+    UnmatchedQuote { quote: char, span: Span },
+    #[error("unexpected end of file in command substitution")]
+    UnterminatedSubstitution { span: Span },
+    #[error("bad substitution: no closing `}}'")]
+    BadSubstitution { span: Span },
+}
+
+impl TokenizeError {
+    pub fn span(&self) -> Span {
+        match self {
+            TokenizeError::UnmatchedQuote { span, .. }
+            | TokenizeError::UnterminatedSubstitution { span }
+            | TokenizeError::BadSubstitution { span } => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseError {
+    #[error("syntax error near unexpected token `{token}'")]
+    UnexpectedToken { token: String, span: Span },
+    #[error("syntax error: unexpected end of file")]
+    UnexpectedEof { span: Span },
+    #[error("syntax error: expected filename after `{op}'")]
+    MissingRedirectTarget { op: String, span: Span },
+    #[error(transparent)]
+    Tokenize(#[from] TokenizeError),
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span }
+            | ParseError::MissingRedirectTarget { span, .. } => *span,
+            ParseError::Tokenize(e) => e.span(),
+        }
+    }
+
+    /// Whether the error only means the input stopped early, so reading
+    /// more lines could complete it.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self,
+            ParseError::UnexpectedEof { .. }
+                | ParseError::Tokenize(TokenizeError::UnmatchedQuote { .. })
+                | ParseError::Tokenize(TokenizeError::UnterminatedSubstitution { .. })
+                | ParseError::Tokenize(TokenizeError::BadSubstitution { .. })
+        )
+    }
+}
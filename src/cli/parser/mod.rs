@@ -1,2 +1,7 @@
+pub mod error;
+pub mod parse_cond;
+pub mod parse_list;
+pub mod parse_param;
 pub mod parse_simple;
 pub mod tokenize;
+pub mod word;
@@ -0,0 +1,248 @@
+use crate::cli::parser::error::{ParseError, Span};
+use crate::cli::parser::parse_simple::{unexpected, word_of};
+use crate::cli::parser::tokenize::{SpannedToken, Token};
+use crate::cli::parser::word::{Word, WordPart};
+use std::fmt;
+
+/// Unary operators understood by `test` and `[[ ]]`.
+pub const UNARY_OPS: &[&str] = &[
+    "-a", "-b", "-c", "-d", "-e", "-f", "-g", "-h", "-k", "-n", "-o", "-p", "-r", "-s", "-t", "-u",
+    "-v", "-w", "-x", "-z", "-G", "-L", "-N", "-O", "-S",
+];
+
+/// Binary operators understood by `[[ ]]`. `test` takes the same ones
+/// apart from `=~`, with `-a` and `-o` as its connectives.
+pub const BINARY_OPS: &[&str] = &[
+    "=", "==", "!=", "=~", "<", ">", "-eq", "-ne", "-lt", "-le", "-gt", "-ge", "-nt", "-ot", "-ef",
+];
+
+/// The expression inside `[[ ... ]]`.
+#[derive(Debug, Clone)]
+pub enum CondExpr {
+    /// A lone word, true when it expands to a non-empty string.
+    Word(Word),
+    Unary(String, Word),
+    Binary(Word, String, Word),
+    Not(Box<CondExpr>),
+    And(Box<CondExpr>, Box<CondExpr>),
+    Or(Box<CondExpr>, Box<CondExpr>),
+    /// `( expr )`, kept so the expression prints as written.
+    Group(Box<CondExpr>),
+}
+
+/// Parses a conditional expression from `tokens[pos..]`, just after the
+/// opening `[[`. Returns the expression and the position after `]]`.
+pub fn parse_conditional(
+    tokens: &[SpannedToken],
+    pos: usize,
+) -> Result<(CondExpr, usize), ParseError> {
+    let mut parser = CondParser { tokens, pos };
+    let expr = parser.or()?;
+    parser.skip_newlines();
+    if !parser.at_close() {
+        return Err(parser.error_here());
+    }
+    Ok((expr, parser.pos + 1))
+}
+
+struct CondParser<'a> {
+    tokens: &'a [SpannedToken],
+    pos: usize,
+}
+
+impl CondParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn at_close(&self) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == "]]")
+    }
+
+    fn error_here(&self) -> ParseError {
+        match self.tokens.get(self.pos) {
+            Some(tok) => unexpected(tok),
+            None => {
+                let end = self.tokens.last().map_or(0, |t| t.span.end);
+                ParseError::UnexpectedEof {
+                    span: Span::new(end, end),
+                }
+            }
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(&Token::Newline) {
+            self.pos += 1;
+        }
+    }
+
+    fn or(&mut self) -> Result<CondExpr, ParseError> {
+        let mut lhs = self.and()?;
+        loop {
+            self.skip_newlines();
+            if self.peek() != Some(&Token::OrIf) {
+                return Ok(lhs);
+            }
+            self.pos += 1;
+            lhs = CondExpr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> Result<CondExpr, ParseError> {
+        let mut lhs = self.not()?;
+        loop {
+            self.skip_newlines();
+            if self.peek() != Some(&Token::AndIf) {
+                return Ok(lhs);
+            }
+            self.pos += 1;
+            lhs = CondExpr::And(Box::new(lhs), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<CondExpr, ParseError> {
+        self.skip_newlines();
+        if matches!(self.peek(), Some(Token::Word(w)) if w == "!") {
+            self.pos += 1;
+            return Ok(CondExpr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<CondExpr, ParseError> {
+        self.skip_newlines();
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.or()?;
+            self.skip_newlines();
+            if self.peek() != Some(&Token::RParen) {
+                return Err(self.error_here());
+            }
+            self.pos += 1;
+            return Ok(CondExpr::Group(Box::new(inner)));
+        }
+
+        let unary = match self.peek() {
+            Some(Token::Word(op)) if UNARY_OPS.contains(&op.as_str()) => Some(op.clone()),
+            _ => None,
+        };
+        let lhs = self.word()?;
+        if let Some(op) = unary {
+            if self.at_operand() {
+                return Ok(CondExpr::Unary(op, self.word()?));
+            }
+        }
+
+        let op = match self.peek() {
+            Some(Token::Word(op)) if BINARY_OPS.contains(&op.as_str()) => op.clone(),
+            Some(Token::RedirectIn) => "<".to_string(),
+            Some(Token::RedirectOut) => ">".to_string(),
+            _ => return Ok(CondExpr::Word(lhs)),
+        };
+        self.pos += 1;
+        let rhs = if op == "=~" {
+            self.regex_word()?
+        } else {
+            self.word()?
+        };
+        Ok(CondExpr::Binary(lhs, op, rhs))
+    }
+
+    fn at_operand(&self) -> bool {
+        matches!(self.peek(), Some(Token::Word(_) | Token::Compound(_))) && !self.at_close()
+    }
+
+    fn word(&mut self) -> Result<Word, ParseError> {
+        if !self.at_operand() {
+            return Err(self.error_here());
+        }
+        let word = word_of(&self.tokens[self.pos].token).unwrap();
+        self.pos += 1;
+        Ok(word)
+    }
+
+    /// The right-hand side of `=~`. Operators that would otherwise end a
+    /// word, such as `|` and parentheses, belong to the regular expression
+    /// as long as no blank separates them, or anywhere inside parentheses.
+    fn regex_word(&mut self) -> Result<Word, ParseError> {
+        let mut parts = vec![];
+        let mut depth = 0usize;
+        let mut end: Option<usize> = None;
+        while let Some(tok) = self.tokens.get(self.pos) {
+            let adjacent = end.map_or(true, |e| tok.span.start == e);
+            if !adjacent && depth == 0 {
+                break;
+            }
+            match &tok.token {
+                Token::Newline => break,
+                Token::Word(w) if w == "]]" && depth == 0 => break,
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                _ => {}
+            }
+            if !adjacent {
+                parts.push(WordPart::Literal(" ".into()));
+            }
+            match word_of(&tok.token) {
+                Some(w) => parts.extend(w.parts),
+                None => parts.push(WordPart::Literal(tok.token.to_string())),
+            }
+            end = Some(tok.span.end);
+            self.pos += 1;
+        }
+        if parts.is_empty() {
+            return Err(self.error_here());
+        }
+        Ok(Word { parts })
+    }
+}
+
+impl fmt::Display for CondExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CondExpr::Word(w) => write!(f, "{}", w),
+            CondExpr::Unary(op, w) => write!(f, "{} {}", op, w),
+            CondExpr::Binary(lhs, op, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+            CondExpr::Not(e) => write!(f, "! {}", e),
+            CondExpr::And(a, b) => write!(f, "{} && {}", a, b),
+            CondExpr::Or(a, b) => write!(f, "{} || {}", a, b),
+            CondExpr::Group(e) => write!(f, "( {} )", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::parser::parse_list::{parse_source, CommandNode};
+
+    fn parse(line: &str) -> String {
+        let list = parse_source(line).unwrap();
+        match &list.items[0].and_or.first.commands[0] {
+            CommandNode::Conditional(expr) => format!("{:?}", expr),
+            other => panic!("not a conditional: {}", other),
+        }
+    }
+
+    #[test]
+    fn precedence_and_grouping() {
+        let list = parse_source("[[ -f x && ( a == b* || ! $v ) ]] && echo y").unwrap();
+        assert_eq!(
+            list.to_string(),
+            "[[ -f x && ( a == b* || ! ${v} ) ]] && echo y"
+        );
+        assert!(parse("[[ a < b ]]").contains("\"<\""));
+        assert!(parse("[[\n-n x\n]]").contains("Unary(\"-n\""));
+        assert!(parse_source("[[ a == ]]").is_err());
+        assert!(parse_source("[[ a\n").unwrap_err().is_incomplete());
+    }
+
+    #[test]
+    fn regex_operand() {
+        let expr = parse("[[ $x =~ ^(a|b)+$ ]]");
+        assert!(expr.contains("Literal(\"|\")"), "{}", expr);
+        let expr = parse("[[ $x =~ (a b) ]]");
+        assert!(expr.contains("Literal(\" \")"), "{}", expr);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::cli::parser::error::{ParseError, Span};
+use crate::cli::parser::parse_cond::{parse_conditional, CondExpr};
+use crate::cli::parser::parse_simple::{
+    is_redirect, parse_command, parse_redirect, unexpected, ParsedCommand, RedirKind, Redirection,
+};
+use crate::cli::parser::tokenize::{ArgvTokenizer, SpannedToken, Token};
+use std::fmt;
+
+/// Words the parser treats specially at the start of a command.
+pub const KEYWORDS: &[&str] = &["!", "{", "}", "[[", "]]"];
+
+#[derive(Debug, Clone)]
+pub enum CommandNode {
+    Simple(ParsedCommand),
+    /// `( list )`, run in a forked child.
+    Subshell(List, Vec<Redirection>),
+    /// `{ list; }`, run in the current shell.
+    Group(List, Vec<Redirection>),
+    /// `[[ expr ]]`, evaluated without field splitting or pathname
+    /// expansion.
+    Conditional(CondExpr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connector {
+    And, // &&
+    Or,  // ||
+}
+
+/// Commands joined by `|`, optionally negated with a leading `!`.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    pub negated: bool,
+    pub commands: Vec<CommandNode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AndOrList {
+    pub first: Pipeline,
+    pub rest: Vec<(Connector, Pipeline)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    pub and_or: AndOrList,
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct List {
+    pub items: Vec<ListItem>,
+}
+
+/// Parses a complete command line (or script) into a list.
+pub fn parse_list(tokens: &[SpannedToken]) -> Result<List, ParseError> {
+    let mut parser = Parser { tokens, pos: 0 };
+    let list = parser.list()?;
+    match parser.peek_spanned() {
+        None => Ok(list),
+        Some(tok) => Err(unexpected(tok)),
+    }
+}
+
+/// Tokenizes and parses `source` in one step.
+pub fn parse_source(source: &str) -> Result<List, ParseError> {
+    parse_list(&ArgvTokenizer::tokenize_spanned(source)?)
+}
+
+/// Whether `line` needs more input to form a complete command: an unclosed
+/// quote, a trailing backslash, an open group or a dangling `&&`/`||`.
+pub fn is_incomplete(line: &str) -> bool {
+    let trimmed = line.strip_suffix('\n').unwrap_or(line);
+    let trailing_backslashes = trimmed.chars().rev().take_while(|&c| c == '\\').count();
+    if trailing_backslashes % 2 == 1 {
+        return true;
+    }
+    matches!(parse_source(line), Err(e) if e.is_incomplete())
+}
+
+struct Parser<'a> {
+    tokens: &'a [SpannedToken],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_spanned().map(|t| &t.token)
+    }
+
+    fn peek_spanned(&self) -> Option<&SpannedToken> {
+        self.tokens.get(self.pos)
+    }
+
+    /// The error for the token at the current position, or for running out
+    /// of input.
+    fn error_here(&self) -> ParseError {
+        match self.peek_spanned() {
+            Some(tok) => unexpected(tok),
+            None => {
+                let end = self.tokens.last().map_or(0, |t| t.span.end);
+                ParseError::UnexpectedEof {
+                    span: Span::new(end, end),
+                }
+            }
+        }
+    }
+
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == word)
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(&Token::Newline) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parses and-or lists separated by `;`, `&` or newlines, stopping at the
+    /// end of input or at a token that closes an enclosing group.
+    fn list(&mut self) -> Result<List, ParseError> {
+        let mut list = List::default();
+        loop {
+            self.skip_newlines();
+            match self.peek() {
+                None | Some(Token::RParen) => break,
+                Some(Token::Word(w)) if w == "}" => break,
+                _ => {}
+            }
+            let and_or = self.and_or()?;
+            let background = match self.peek() {
+                Some(Token::Ampersand) => true,
+                Some(Token::Semicolon) | Some(Token::Newline) => false,
+                _ => {
+                    list.items.push(ListItem {
+                        and_or,
+                        background: false,
+                    });
+                    break;
+                }
+            };
+            self.pos += 1;
+            list.items.push(ListItem { and_or, background });
+        }
+        Ok(list)
+    }
+
+    fn and_or(&mut self) -> Result<AndOrList, ParseError> {
+        let first = self.pipeline()?;
+        let mut rest = vec![];
+        loop {
+            let connector = match self.peek() {
+                Some(Token::AndIf) => Connector::And,
+                Some(Token::OrIf) => Connector::Or,
+                _ => break,
+            };
+            self.pos += 1;
+            self.skip_newlines();
+            rest.push((connector, self.pipeline()?));
+        }
+        Ok(AndOrList { first, rest })
+    }
+
+    fn pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let negated = self.peek_word("!");
+        if negated {
+            self.pos += 1;
+        }
+        let mut commands = vec![self.command()?];
+        while self.peek() == Some(&Token::Pipe) {
+            self.pos += 1;
+            self.skip_newlines();
+            commands.push(self.command()?);
+        }
+        Ok(Pipeline { negated, commands })
+    }
+
+    fn command(&mut self) -> Result<CommandNode, ParseError> {
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let body = self.compound_body()?;
+            if self.peek() != Some(&Token::RParen) {
+                return Err(self.error_here());
+            }
+            self.pos += 1;
+            return Ok(CommandNode::Subshell(body, self.redirects()?));
+        }
+        if self.peek_word("{") {
+            self.pos += 1;
+            let body = self.compound_body()?;
+            if !self.peek_word("}") {
+                return Err(self.error_here());
+            }
+            self.pos += 1;
+            return Ok(CommandNode::Group(body, self.redirects()?));
+        }
+        if self.peek_word("[[") {
+            let (expr, next) = parse_conditional(self.tokens, self.pos + 1)?;
+            self.pos = next;
+            return Ok(CommandNode::Conditional(expr));
+        }
+
+        let start = self.pos;
+        while let Some(tok) = self.peek() {
+            if is_redirect(tok) {
+                self.pos += 2;
+            } else if matches!(tok, Token::Word(_) | Token::Compound(_)) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let end = self.pos.min(self.tokens.len());
+        if start == end {
+            return Err(self.error_here());
+        }
+        Ok(CommandNode::Simple(parse_command(
+            &self.tokens[start..end],
+        )?))
+    }
+
+    fn compound_body(&mut self) -> Result<List, ParseError> {
+        let body = self.list()?;
+        if body.items.is_empty() {
+            return Err(self.error_here());
+        }
+        Ok(body)
+    }
+
+    fn redirects(&mut self) -> Result<Vec<Redirection>, ParseError> {
+        let mut redirects = vec![];
+        while let Some(tok) = self.peek_spanned().filter(|t| is_redirect(&t.token)) {
+            redirects.push(parse_redirect(tok, self.tokens.get(self.pos + 1))?);
+            self.pos += 2;
+        }
+        Ok(redirects)
+    }
+}
+
+impl fmt::Display for Redirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.kind {
+            RedirKind::StdoutTruncate => ">",
+            RedirKind::StdoutAppend => ">>",
+            RedirKind::StdoutClobber => ">|",
+            RedirKind::StderrTruncate => "2>",
+            RedirKind::StderrAppend => "2>>",
+            RedirKind::StderrClobber => "2>|",
+            RedirKind::Stdin => "<",
+        };
+        write!(f, "{} {}", op, self.target)
+    }
+}
+
+fn write_redirects(f: &mut fmt::Formatter<'_>, redirects: &[Redirection]) -> fmt::Result {
+    for redir in redirects {
+        write!(f, " {}", redir)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ParsedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<String> = self
+            .assignments
+            .iter()
+            .map(|a| a.to_string())
+            .chain(self.argv.iter().map(|w| w.to_string()))
+            .collect();
+        write!(f, "{}", words.join(" "))?;
+        write_redirects(f, &self.redirects)
+    }
+}
+
+impl fmt::Display for CommandNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandNode::Simple(cmd) => write!(f, "{}", cmd),
+            CommandNode::Subshell(body, redirects) => {
+                write!(f, "( {} )", body)?;
+                write_redirects(f, redirects)
+            }
+            CommandNode::Group(body, redirects) => {
+                write!(f, "{{ {}; }}", body)?;
+                write_redirects(f, redirects)
+            }
+            CommandNode::Conditional(expr) => write!(f, "[[ {} ]]", expr),
+        }
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "! ")?;
+        }
+        for (i, cmd) in self.commands.iter().enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            write!(f, "{}", cmd)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AndOrList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for (connector, cmd) in &self.rest {
+            let op = match connector {
+                Connector::And => "&&",
+                Connector::Or => "||",
+            };
+            write!(f, " {} {}", op, cmd)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", item.and_or)?;
+            if item.background {
+                write!(f, " &")?;
+            } else if i + 1 < self.items.len() {
+                write!(f, ";")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<List, String> {
+        parse_source(line).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn groups_and_subshells() {
+        let list = parse("{ echo a; echo b; } > out && (cd /tmp; ls) &\n").unwrap();
+        assert_eq!(list.items.len(), 1);
+        assert!(list.items[0].background);
+        assert_eq!(
+            list.to_string(),
+            "{ echo a; echo b; } > out && ( cd /tmp; ls ) &"
+        );
+    }
+
+    #[test]
+    fn pipelines() {
+        let list = parse("! grep -q x file | wc -l && echo \"$HOME\"").unwrap();
+        let and_or = &list.items[0].and_or;
+        assert!(and_or.first.negated);
+        assert_eq!(and_or.first.commands.len(), 2);
+        assert_eq!(
+            list.to_string(),
+            "! grep -q x file | wc -l && echo \"${HOME}\""
+        );
+    }
+
+    #[test]
+    fn closing_brace_is_only_reserved_at_command_start() {
+        let list = parse("{ echo }; }").unwrap();
+        assert_eq!(list.to_string(), "{ echo }; }");
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            parse("echo a; )").unwrap_err(),
+            "syntax error near unexpected token `)'"
+        );
+        assert_eq!(
+            parse("( )").unwrap_err(),
+            "syntax error near unexpected token `)'"
+        );
+        assert_eq!(
+            parse("&& ls").unwrap_err(),
+            "syntax error near unexpected token `&&'"
+        );
+    }
+
+    #[test]
+    fn error_spans() {
+        let err = parse_source("echo a; )").unwrap_err();
+        assert_eq!(err.span(), Span::new(8, 9));
+        let err = parse_source("ls >").unwrap_err();
+        assert_eq!(err.to_string(), "syntax error: expected filename after `>'");
+        assert_eq!(err.span(), Span::new(4, 4));
+        let err = parse_source("{ echo a").unwrap_err();
+        assert!(err.is_incomplete());
+        assert_eq!(err.span(), Span::new(8, 8));
+    }
+
+    #[test]
+    fn incomplete_input() {
+        assert!(is_incomplete("{ echo a\n"));
+        assert!(is_incomplete("(cd x &&\n"));
+        assert!(is_incomplete("echo 'abc\n"));
+        assert!(is_incomplete("echo abc \\\n"));
+        assert!(is_incomplete("ls |\n"));
+        assert!(is_incomplete("echo $(pwd\n"));
+        assert!(!is_incomplete("{ echo a; }\n"));
+        assert!(!is_incomplete("echo a; )\n"));
+    }
+}
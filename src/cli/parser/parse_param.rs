@@ -0,0 +1,321 @@
+use crate::cli::vars::is_name;
+
+/// What a `${...}` expansion does to the parameter it names. Operands are
+/// kept as written; they are only expanded if the operator needs them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamOp {
+    /// `${name}`
+    Plain,
+    /// `${#name}`
+    Length,
+    /// `${name-word}`, `${name=word}`, `${name?word}` and `${name+word}`,
+    /// which with `colon` also treat an empty value as unset.
+    Default {
+        kind: DefaultKind,
+        colon: bool,
+        word: String,
+    },
+    /// `${name#pattern}`, or `${name##pattern}` when `longest`.
+    RemovePrefix { pattern: String, longest: bool },
+    /// `${name%pattern}`, or `${name%%pattern}` when `longest`.
+    RemoveSuffix { pattern: String, longest: bool },
+    /// `${name/pattern/replacement}` and its `//`, `/#` and `/%` forms.
+    Replace {
+        mode: ReplaceMode,
+        pattern: String,
+        replacement: String,
+    },
+    /// `${name:offset}` or `${name:offset:length}`.
+    Substring {
+        offset: String,
+        length: Option<String>,
+    },
+    /// `${name^pattern}`, `${name^^pattern}`, `${name,pattern}` and
+    /// `${name,,pattern}`.
+    Case {
+        upper: bool,
+        all: bool,
+        pattern: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DefaultKind {
+    /// `-`: use the word instead.
+    Use,
+    /// `=`: assign the word, then use it.
+    Assign,
+    /// `?`: fail with the word as the message.
+    Error,
+    /// `+`: use the word only if the parameter is set.
+    Alternate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaceMode {
+    First,
+    All,
+    Prefix,
+    Suffix,
+}
+
+/// The body of a `${...}` expansion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamExpr {
+    pub name: String,
+    /// `${name[subscript]}`: an element of an array, or all of them for
+    /// `@` and `*`.
+    pub subscript: Option<String>,
+    /// `${!name}`: the parameter is the one whose name is `name`'s value.
+    pub indirect: bool,
+    pub op: ParamOp,
+}
+
+/// Parses the text between `${` and `}`, returning `None` for a bad
+/// substitution.
+pub fn parse_param(text: &str) -> Option<ParamExpr> {
+    // `${#}` is `$#`, while `${#name}` is a length.
+    if let Some(rest) = text.strip_prefix('#').filter(|r| !r.is_empty()) {
+        let (name, tail) = split_name(rest)?;
+        let (subscript, tail) = split_subscript(name, tail)?;
+        return tail.is_empty().then(|| ParamExpr {
+            name: name.to_string(),
+            subscript,
+            indirect: false,
+            op: ParamOp::Length,
+        });
+    }
+    // `${!name}` is indirect, while in `${!}` and `${!:+word}` the `!` is
+    // the special parameter.
+    let (indirect, rest) = match text.strip_prefix('!').filter(|r| split_name(r).is_some()) {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (name, tail) = split_name(rest)?;
+    let (subscript, tail) = split_subscript(name, tail)?;
+    Some(ParamExpr {
+        name: name.to_string(),
+        subscript,
+        indirect,
+        op: parse_op(tail)?,
+    })
+}
+
+/// Splits a `[subscript]` off the front of `tail` if `name` can be an
+/// array. Fails if the bracket is not closed.
+fn split_subscript<'a>(name: &str, tail: &'a str) -> Option<(Option<String>, &'a str)> {
+    let Some(rest) = tail.strip_prefix('[').filter(|_| is_name(name)) else {
+        return Some((None, tail));
+    };
+    let mut depth = 1;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((Some(rest[..i].to_string()), &rest[i + 1..]));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits a parameter name, number or special parameter off the front of
+/// `s`.
+fn split_name(s: &str) -> Option<(&str, &str)> {
+    let first = s.chars().next()?;
+    let end = if first == '_' || first.is_ascii_alphabetic() {
+        s.find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+            .unwrap_or(s.len())
+    } else if first.is_ascii_digit() {
+        s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len())
+    } else if "@*#?$!-".contains(first) {
+        1
+    } else {
+        return None;
+    };
+    Some(s.split_at(end))
+}
+
+fn parse_op(tail: &str) -> Option<ParamOp> {
+    if tail.is_empty() {
+        return Some(ParamOp::Plain);
+    }
+    let (colon, rest) = match tail.strip_prefix(':') {
+        Some(rest) => (true, rest),
+        None => (false, tail),
+    };
+    let kind = match rest.chars().next() {
+        Some('-') => Some(DefaultKind::Use),
+        Some('=') => Some(DefaultKind::Assign),
+        Some('?') => Some(DefaultKind::Error),
+        Some('+') => Some(DefaultKind::Alternate),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        return Some(ParamOp::Default {
+            kind,
+            colon,
+            word: rest[1..].to_string(),
+        });
+    }
+    if colon {
+        let (offset, length) = split_top(rest, ':');
+        return Some(ParamOp::Substring {
+            offset: offset.to_string(),
+            length: length.map(str::to_string),
+        });
+    }
+
+    let strip = |prefix: &str| tail.strip_prefix(prefix).map(str::to_string);
+    if let Some(pattern) = strip("##") {
+        return Some(ParamOp::RemovePrefix {
+            pattern,
+            longest: true,
+        });
+    }
+    if let Some(pattern) = strip("#") {
+        return Some(ParamOp::RemovePrefix {
+            pattern,
+            longest: false,
+        });
+    }
+    if let Some(pattern) = strip("%%") {
+        return Some(ParamOp::RemoveSuffix {
+            pattern,
+            longest: true,
+        });
+    }
+    if let Some(pattern) = strip("%") {
+        return Some(ParamOp::RemoveSuffix {
+            pattern,
+            longest: false,
+        });
+    }
+    if let Some(rest) = tail.strip_prefix('/') {
+        let (mode, rest) = match rest.chars().next() {
+            Some('/') => (ReplaceMode::All, &rest[1..]),
+            Some('#') => (ReplaceMode::Prefix, &rest[1..]),
+            Some('%') => (ReplaceMode::Suffix, &rest[1..]),
+            _ => (ReplaceMode::First, rest),
+        };
+        let (pattern, replacement) = split_top(rest, '/');
+        return Some(ParamOp::Replace {
+            mode,
+            pattern: pattern.to_string(),
+            replacement: replacement.unwrap_or_default().to_string(),
+        });
+    }
+    for (prefix, upper, all) in [
+        ("^^", true, true),
+        ("^", true, false),
+        (",,", false, true),
+        (",", false, false),
+    ] {
+        if let Some(pattern) = strip(prefix) {
+            return Some(ParamOp::Case {
+                upper,
+                all,
+                pattern,
+            });
+        }
+    }
+    None
+}
+
+/// Splits `s` at the first `sep` that is not quoted, escaped or inside a
+/// nested expansion.
+fn split_top(s: &str, sep: char) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut depth = 0;
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '{' | '(') => depth += 1,
+            (None, '}' | ')') => depth -= 1,
+            (None, c) if c == sep && depth == 0 => return (&s[..i], Some(&s[i + 1..])),
+            _ => {}
+        }
+    }
+    (s, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(text: &str) -> Option<ParamOp> {
+        parse_param(text).map(|e| e.op)
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(op("x"), Some(ParamOp::Plain));
+        assert_eq!(parse_param("#").unwrap().name, "#");
+        assert_eq!(op("#x"), Some(ParamOp::Length));
+        assert_eq!(
+            op("x:-a b"),
+            Some(ParamOp::Default {
+                kind: DefaultKind::Use,
+                colon: true,
+                word: "a b".into()
+            })
+        );
+        assert_eq!(
+            op("10%%.*"),
+            Some(ParamOp::RemoveSuffix {
+                pattern: ".*".into(),
+                longest: true
+            })
+        );
+        assert_eq!(
+            op("x//'/'/${y/a/b}"),
+            Some(ParamOp::Replace {
+                mode: ReplaceMode::All,
+                pattern: "'/'".into(),
+                replacement: "${y/a/b}".into()
+            })
+        );
+        assert_eq!(
+            op("x: -2:1"),
+            Some(ParamOp::Substring {
+                offset: " -2".into(),
+                length: Some("1".into())
+            })
+        );
+        let expr = parse_param("!ref,,").unwrap();
+        assert!(expr.indirect);
+        assert_eq!(expr.name, "ref");
+        let expr = parse_param("!:+x").unwrap();
+        assert!(!expr.indirect);
+        assert_eq!(expr.name, "!");
+        assert_eq!(
+            op("x^"),
+            Some(ParamOp::Case {
+                upper: true,
+                all: false,
+                pattern: String::new()
+            })
+        );
+        let expr = parse_param("#a[i+1]").unwrap();
+        assert_eq!(expr.subscript.as_deref(), Some("i+1"));
+        assert_eq!(expr.op, ParamOp::Length);
+        let expr = parse_param("a[@]:1:2").unwrap();
+        assert_eq!(expr.subscript.as_deref(), Some("@"));
+        assert!(matches!(expr.op, ParamOp::Substring { .. }));
+        assert_eq!(op("a[@"), None);
+        assert_eq!(op("x y"), None);
+        assert_eq!(op("#x-"), None);
+        assert_eq!(op(""), None);
+    }
+}
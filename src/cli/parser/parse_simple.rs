@@ -1,84 +1,306 @@
-use crate::cli::parser::tokenize::Token;
+use crate::cli::parser::error::{ParseError, Span};
+use crate::cli::parser::tokenize::{ArgvTokenizer, SpannedToken, Token};
+use crate::cli::parser::word::{Word, WordPart};
+use crate::cli::vars::is_name;
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RedirKind {
     StdoutTruncate, // >
     StdoutAppend,   // >>
+    StdoutClobber,  // >|
     StderrTruncate, // 2>
     StderrAppend,   // 2>>
+    StderrClobber,  // 2>|
     Stdin,          // <
 }
 
+#[derive(Debug, Clone)]
 pub struct Redirection {
     pub kind: RedirKind,
-    pub target: String,
+    pub target: Word,
 }
 
+/// A `name=value` word before the command name, or `name[subscript]=value`
+/// for an element of an array. `name+=value` appends to the old value.
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub name: String,
+    pub subscript: Option<Word>,
+    pub append: bool,
+    pub value: AssignedValue,
+}
+
+#[derive(Debug, Clone)]
+pub enum AssignedValue {
+    Scalar(Word),
+    /// `name=(...)`.
+    Array(Vec<ArrayItem>),
+}
+
+/// An item of `name=(...)`, which may give its own `[subscript]=`.
+#[derive(Debug, Clone)]
+pub struct ArrayItem {
+    pub subscript: Option<Word>,
+    pub value: Word,
+}
+
+impl Assignment {
+    /// Whether any of the words run a command substitution.
+    pub fn substitutes(&self) -> bool {
+        let words: Vec<&Word> = match &self.value {
+            AssignedValue::Scalar(word) => vec![word],
+            AssignedValue::Array(items) => items.iter().map(|i| &i.value).collect(),
+        };
+        words
+            .into_iter()
+            .chain(&self.subscript)
+            .flat_map(|w| &w.parts)
+            .any(|p| matches!(p, WordPart::CommandSub { .. }))
+    }
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(sub) = &self.subscript {
+            write!(f, "[{}]", sub)?;
+        }
+        write!(f, "{}=", if self.append { "+" } else { "" })?;
+        match &self.value {
+            AssignedValue::Scalar(word) => write!(f, "{}", word),
+            AssignedValue::Array(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| match &item.subscript {
+                        Some(sub) => format!("[{}]={}", sub, item.value),
+                        None => item.value.to_string(),
+                    })
+                    .collect();
+                write!(f, "({})", items.join(" "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ParsedCommand {
-    pub argv: Vec<String>,
+    pub assignments: Vec<Assignment>,
+    pub argv: Vec<Word>,
     pub redirects: Vec<Redirection>,
 }
 
-pub fn parse_command(tokens: &[Token]) -> Result<ParsedCommand, String> {
+pub fn parse_command(tokens: &[SpannedToken]) -> Result<ParsedCommand, ParseError> {
+    let mut assignments = vec![];
     let mut argv = vec![];
     let mut redirects = vec![];
 
-    let mut iter = tokens.iter().peekable();
+    let mut iter = tokens.iter();
 
     while let Some(tok) = iter.next() {
-        match tok {
-            Token::Word(w) => argv.push(w.clone()),
-            Token::RedirectOut => {
-                let target = match iter.next() {
-                    Some(Token::Word(w)) => w.clone(),
-                    _ => return Err("expected filename after '>'".into()),
-                };
-                redirects.push(Redirection {
-                    kind: RedirKind::StdoutTruncate,
-                    target,
-                });
-            }
-            Token::RedirectOutAppend => {
-                let target = match iter.next() {
-                    Some(Token::Word(w)) => w.clone(),
-                    _ => return Err("expected filename after '>>'".into()),
+        match &tok.token {
+            Token::Word(_) | Token::Compound(_) => {
+                let word = word_of(&tok.token).unwrap();
+                let assignment = match argv.is_empty() {
+                    true => assignment_of(&word)?,
+                    false => None,
                 };
-                redirects.push(Redirection {
-                    kind: RedirKind::StdoutAppend,
-                    target,
-                });
+                match assignment {
+                    Some(assignment) => assignments.push(assignment),
+                    None => argv.push(word),
+                }
             }
-            Token::RedirectErr => {
-                let target = match iter.next() {
-                    Some(Token::Word(w)) => w.clone(),
-                    _ => return Err("expected filename after '2>'".into()),
-                };
-                redirects.push(Redirection {
-                    kind: RedirKind::StderrTruncate,
-                    target,
-                });
-            }
-            Token::RedirectErrAppend => {
-                let target = match iter.next() {
-                    Some(Token::Word(w)) => w.clone(),
-                    _ => return Err("expected filename after '2>>'".into()),
-                };
-                redirects.push(Redirection {
-                    kind: RedirKind::StderrAppend,
-                    target,
-                });
+            _ => redirects.push(parse_redirect(tok, iter.next())?),
+        }
+    }
+    Ok(ParsedCommand {
+        assignments,
+        argv,
+        redirects,
+    })
+}
+
+/// Splits an assignment word at its first `=`. The name, and the `=`,
+/// must be unquoted. An array is assigned if the tokenizer kept a
+/// parenthesised list after the `=`.
+pub fn assignment_of(word: &Word) -> Result<Option<Assignment>, ParseError> {
+    let Some(WordPart::Literal(first)) = word.parts.first() else {
+        return Ok(None);
+    };
+    let len = first
+        .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+        .unwrap_or(first.len());
+    if !is_name(&first[..len]) {
+        return Ok(None);
+    }
+    let name = first[..len].to_string();
+    let (_, mut rest) = split_parts(&word.parts, 0, len);
+    let subscript = match split_subscript(&rest) {
+        Some((sub, after)) => {
+            rest = after;
+            Some(sub)
+        }
+        None => None,
+    };
+    let Some(WordPart::Literal(op)) = rest.first() else {
+        return Ok(None);
+    };
+    let append = op.starts_with("+=");
+    if !append && !op.starts_with('=') {
+        return Ok(None);
+    }
+    let (_, value) = split_parts(&rest, 0, if append { 2 } else { 1 });
+    let value = match value.as_slice() {
+        [WordPart::Literal(text)]
+            if subscript.is_none() && text.starts_with('(') && text.ends_with(')') =>
+        {
+            AssignedValue::Array(parse_array(&text[1..text.len() - 1])?)
+        }
+        _ => AssignedValue::Scalar(Word { parts: value }),
+    };
+    Ok(Some(Assignment {
+        name,
+        subscript,
+        append,
+        value,
+    }))
+}
+
+/// Splits the items of `name=(...)`, any of which may start with
+/// `[subscript]=`.
+pub fn parse_array(text: &str) -> Result<Vec<ArrayItem>, ParseError> {
+    let mut items = vec![];
+    for tok in ArgvTokenizer::tokenize_spanned(text)? {
+        if tok.token == Token::Newline {
+            continue;
+        }
+        let word = word_of(&tok.token).ok_or_else(|| unexpected(&tok))?;
+        let item = match split_subscript(&word.parts) {
+            Some((sub, rest)) if matches!(rest.first(), Some(WordPart::Literal(t)) if t.starts_with('=')) => {
+                ArrayItem {
+                    subscript: Some(sub),
+                    value: Word {
+                        parts: split_parts(&rest, 0, 1).1,
+                    },
+                }
             }
-            Token::RedirectIn => {
-                let target = match iter.next() {
-                    Some(Token::Word(w)) => w.clone(),
-                    _ => return Err("expected filename after '<'".into()),
-                };
-                redirects.push(Redirection {
-                    kind: RedirKind::Stdin,
-                    target,
-                });
+            _ => ArrayItem {
+                subscript: None,
+                value: word,
+            },
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Splits `[subscript]` off the front of `parts`, returning the subscript
+/// and the parts after the `]`. Only unquoted brackets count.
+fn split_subscript(parts: &[WordPart]) -> Option<(Word, Vec<WordPart>)> {
+    let Some(WordPart::Literal(first)) = parts.first() else {
+        return None;
+    };
+    if !first.starts_with('[') {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, part) in parts.iter().enumerate() {
+        let WordPart::Literal(text) = part else {
+            continue;
+        };
+        for (j, c) in text.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let (inner, rest) = split_parts(parts, i, j);
+                        let subscript = Word {
+                            parts: split_parts(&inner, 0, 1).1,
+                        };
+                        return Some((subscript, split_parts(&rest, 0, 1).1));
+                    }
+                }
+                _ => {}
             }
         }
     }
-    Ok(ParsedCommand { argv, redirects })
+    None
+}
+
+/// Splits `parts` at byte `at` of part `i`, which must be a literal.
+fn split_parts(parts: &[WordPart], i: usize, at: usize) -> (Vec<WordPart>, Vec<WordPart>) {
+    let WordPart::Literal(text) = &parts[i] else {
+        unreachable!("only literal text is split");
+    };
+    let mut before = parts[..i].to_vec();
+    let mut after = vec![];
+    if at > 0 {
+        before.push(WordPart::Literal(text[..at].to_string()));
+    }
+    if at < text.len() {
+        after.push(WordPart::Literal(text[at..].to_string()));
+    }
+    after.extend_from_slice(&parts[i + 1..]);
+    (before, after)
+}
+
+/// Builds a redirection from an operator token and the token naming its
+/// target.
+pub fn parse_redirect(
+    op: &SpannedToken,
+    target: Option<&SpannedToken>,
+) -> Result<Redirection, ParseError> {
+    let kind = match op.token {
+        Token::RedirectOut => RedirKind::StdoutTruncate,
+        Token::RedirectOutAppend => RedirKind::StdoutAppend,
+        Token::RedirectClobber => RedirKind::StdoutClobber,
+        Token::RedirectErr => RedirKind::StderrTruncate,
+        Token::RedirectErrAppend => RedirKind::StderrAppend,
+        Token::RedirectErrClobber => RedirKind::StderrClobber,
+        Token::RedirectIn => RedirKind::Stdin,
+        _ => return Err(unexpected(op)),
+    };
+    let target = match target.and_then(|t| word_of(&t.token)) {
+        Some(w) => w,
+        None => {
+            return Err(ParseError::MissingRedirectTarget {
+                op: op.token.to_string(),
+                span: match target {
+                    Some(t) => t.span,
+                    None => Span::new(op.span.end, op.span.end),
+                },
+            })
+        }
+    };
+    Ok(Redirection { kind, target })
+}
+
+pub fn unexpected(tok: &SpannedToken) -> ParseError {
+    ParseError::UnexpectedToken {
+        token: tok.token.to_string(),
+        span: tok.span,
+    }
+}
+
+/// The word carried by a `Word` or `Compound` token.
+pub fn word_of(tok: &Token) -> Option<Word> {
+    match tok {
+        Token::Word(w) => Some(Word::literal(w)),
+        Token::Compound(w) => Some(w.clone()),
+        _ => None,
+    }
+}
+
+pub fn is_redirect(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::RedirectIn
+            | Token::RedirectOut
+            | Token::RedirectOutAppend
+            | Token::RedirectClobber
+            | Token::RedirectErr
+            | Token::RedirectErrAppend
+            | Token::RedirectErrClobber
+    )
 }
@@ -1,19 +1,161 @@
+use crate::cli::parser::error::{Span, TokenizeError};
+use crate::cli::parser::word::{Word, WordPart};
+use crate::cli::vars::is_name;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum Token {
     Word(String),
-    RedirectIn,        // <
-    RedirectOut,       // >
-    RedirectOutAppend, // >>
-    RedirectErr,       // 2>
-    RedirectErrAppend, // 2>>
+    /// A word whose quoting or expansions must be preserved until it is
+    /// expanded; see `Word::simplify`.
+    Compound(Word),
+    RedirectIn,         // <
+    RedirectOut,        // >
+    RedirectOutAppend,  // >>
+    RedirectClobber,    // >|
+    RedirectErr,        // 2>
+    RedirectErrAppend,  // 2>>
+    RedirectErrClobber, // 2>|
+    Pipe,               // |
+    Semicolon,          // ;
+    Ampersand,          // &
+    AndIf,              // &&
+    OrIf,               // ||
+    LParen,             // (
+    RParen,             // )
+    Newline,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Compound(w) => write!(f, "{}", w),
+            Token::RedirectIn => write!(f, "<"),
+            Token::RedirectOut => write!(f, ">"),
+            Token::RedirectOutAppend => write!(f, ">>"),
+            Token::RedirectClobber => write!(f, ">|"),
+            Token::RedirectErr => write!(f, "2>"),
+            Token::RedirectErrAppend => write!(f, "2>>"),
+            Token::RedirectErrClobber => write!(f, "2>|"),
+            Token::Pipe => write!(f, "|"),
+            Token::Semicolon => write!(f, ";"),
+            Token::Ampersand => write!(f, "&"),
+            Token::AndIf => write!(f, "&&"),
+            Token::OrIf => write!(f, "||"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Newline => write!(f, "newline"),
+        }
+    }
+}
+
+/// A token along with the byte range of the input it was read from.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+/// Character iterator that knows the byte offset of the next character.
+struct Cursor<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+}
+
+impl Cursor<'_> {
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn pos(&mut self) -> usize {
+        self.chars.peek().map_or(self.len, |&(i, _)| i)
+    }
+}
+
+/// Accumulates the parts of the word currently being read.
+#[derive(Default)]
+struct WordBuilder {
+    parts: Vec<WordPart>,
+    quoted: bool,
+    start: Option<usize>,
+}
+
+impl WordBuilder {
+    fn begin(&mut self, at: usize) {
+        self.start.get_or_insert(at);
+    }
+
+    fn push_literal(&mut self, c: char) {
+        match self.parts.last_mut() {
+            Some(WordPart::Literal(s)) => s.push(c),
+            _ => self.parts.push(WordPart::Literal(c.to_string())),
+        }
+    }
+
+    fn push_quoted(&mut self, c: char) {
+        self.quoted = true;
+        match self.parts.last_mut() {
+            Some(WordPart::Quoted(s)) => s.push(c),
+            _ => self.parts.push(WordPart::Quoted(c.to_string())),
+        }
+    }
+
+    /// Whether the word so far is `name=` or `name+=`.
+    fn is_array_assignment(&self) -> bool {
+        match self.parts.as_slice() {
+            [WordPart::Literal(s)] => s
+                .strip_suffix('=')
+                .map(|s| s.strip_suffix('+').unwrap_or(s))
+                .is_some_and(is_name),
+            _ => false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.parts.is_empty() && !self.quoted
+    }
+
+    fn flush(&mut self, out: &mut Vec<SpannedToken>, end: usize) {
+        let start = self.start.take();
+        if self.is_empty() {
+            return;
+        }
+        self.quoted = false;
+        let mut parts = std::mem::take(&mut self.parts);
+        if parts.is_empty() {
+            // an empty quoted string such as '' or ""
+            parts.push(WordPart::Quoted(String::new()));
+        }
+        let token = match (Word { parts }).simplify() {
+            Ok(text) => Token::Word(text),
+            Err(word) => Token::Compound(word),
+        };
+        out.push(SpannedToken {
+            token,
+            span: Span::new(start.unwrap_or(end), end),
+        });
+    }
 }
 
 pub struct ArgvTokenizer;
 
 impl ArgvTokenizer {
-    pub fn tokenize(line: &str) -> Result<Vec<Token>, String> {
-        use std::borrow::Cow;
+    #[allow(dead_code)]
+    pub fn tokenize(line: &str) -> Result<Vec<Token>, TokenizeError> {
+        Ok(Self::tokenize_spanned(line)?
+            .into_iter()
+            .map(|t| t.token)
+            .collect())
+    }
 
+    pub fn tokenize_spanned(line: &str) -> Result<Vec<SpannedToken>, TokenizeError> {
         #[derive(Copy, Clone, PartialEq)]
         enum Mode {
             Normal,
@@ -21,112 +163,382 @@ impl ArgvTokenizer {
             InDouble,
         }
         let mut mode = Mode::Normal;
+        let mut quote_start = 0;
         let mut out = vec![];
-        let mut buf = String::new();
-        let mut chars = line.chars().peekable();
+        let mut buf = WordBuilder::default();
+        let mut chars = Cursor {
+            chars: line.char_indices().peekable(),
+            len: line.len(),
+        };
 
-        let flush = |out: &mut Vec<Token>, buf: &mut String| {
-            if !buf.is_empty() {
-                out.push(Token::Word(std::mem::take(buf)));
-            }
+        let push = |out: &mut Vec<SpannedToken>, token: Token, start: usize, end: usize| {
+            out.push(SpannedToken {
+                token,
+                span: Span::new(start, end),
+            });
         };
 
-        while let Some(ch) = chars.next() {
+        loop {
+            let at = chars.pos();
+            let Some(ch) = chars.next() else {
+                break;
+            };
             match mode {
                 Mode::Normal => {
                     match ch {
-                        '\'' => mode = Mode::InSingle,
-                        '"' => mode = Mode::InDouble,
+                        '\'' => {
+                            buf.begin(at);
+                            mode = Mode::InSingle;
+                            quote_start = at;
+                            buf.quoted = true;
+                        }
+                        '"' => {
+                            buf.begin(at);
+                            mode = Mode::InDouble;
+                            quote_start = at;
+                            buf.quoted = true;
+                        }
                         '\\' => {
-                            if let Some(n) = chars.next() {
-                                buf.push(n);
+                            // backslash-newline is a line continuation
+                            if let Some(n) = chars.next().filter(|&n| n != '\n') {
+                                buf.begin(at);
+                                buf.push_quoted(n);
                             }
                         }
+                        '#' if buf.is_empty() => {
+                            // comment, up to but not including the newline
+                            while chars.peek().is_some_and(|c| c != '\n') {
+                                chars.next();
+                            }
+                        }
+                        '$' => {
+                            buf.begin(at);
+                            match Self::dollar(&mut chars, false, at)? {
+                                Some(part) => buf.parts.push(part),
+                                None => buf.push_literal('$'),
+                            }
+                        }
+                        '`' => {
+                            buf.begin(at);
+                            buf.parts.push(Self::backquote(&mut chars, false, at)?);
+                        }
                         '2' => {
-                            if let Some('>') = chars.peek().copied() {
+                            if let Some('>') = chars.peek() {
                                 chars.next(); // consume '>'
-                                flush(&mut out, &mut buf);
-
-                                // check for >>
-                                if let Some('>') = chars.peek().copied() {
-                                    chars.next(); // consume '>'
-                                    out.push(Token::RedirectErrAppend);
-                                } else {
-                                    out.push(Token::RedirectErr);
+                                buf.flush(&mut out, at);
+
+                                // check for >> and >|
+                                match chars.peek() {
+                                    Some(c @ ('>' | '|')) => {
+                                        chars.next();
+                                        let tok = if c == '>' {
+                                            Token::RedirectErrAppend
+                                        } else {
+                                            Token::RedirectErrClobber
+                                        };
+                                        push(&mut out, tok, at, chars.pos());
+                                    }
+                                    _ => push(&mut out, Token::RedirectErr, at, chars.pos()),
                                 }
                             } else {
-                                buf.push('2');
+                                buf.begin(at);
+                                buf.push_literal('2');
                             }
                         }
                         '1' => {
-                            if let Some('>') = chars.peek().copied() {
+                            if let Some('>') = chars.peek() {
                                 chars.next(); // consume '>'
-                                flush(&mut out, &mut buf);
-
-                                // check for >>
-                                if let Some('>') = chars.peek().copied() {
-                                    chars.next(); // consume '>'
-                                    out.push(Token::RedirectOutAppend);
-                                } else {
-                                    out.push(Token::RedirectOut);
+                                buf.flush(&mut out, at);
+
+                                // check for >> and >|
+                                match chars.peek() {
+                                    Some(c @ ('>' | '|')) => {
+                                        chars.next();
+                                        let tok = if c == '>' {
+                                            Token::RedirectOutAppend
+                                        } else {
+                                            Token::RedirectClobber
+                                        };
+                                        push(&mut out, tok, at, chars.pos());
+                                    }
+                                    _ => push(&mut out, Token::RedirectOut, at, chars.pos()),
                                 }
                             } else {
-                                buf.push('1');
+                                buf.begin(at);
+                                buf.push_literal('1');
                             }
                         }
                         '>' => {
-                            flush(&mut out, &mut buf);
+                            buf.flush(&mut out, at);
 
-                            if let Some('>') = chars.peek().copied() {
+                            match chars.peek() {
+                                Some('>') => {
+                                    chars.next();
+                                    push(&mut out, Token::RedirectOutAppend, at, chars.pos());
+                                }
+                                Some('|') => {
+                                    chars.next();
+                                    push(&mut out, Token::RedirectClobber, at, chars.pos());
+                                }
+                                _ => push(&mut out, Token::RedirectOut, at, chars.pos()),
+                            }
+                        }
+                        '<' => {
+                            buf.flush(&mut out, at);
+                            push(&mut out, Token::RedirectIn, at, chars.pos());
+                        }
+                        ';' => {
+                            buf.flush(&mut out, at);
+                            push(&mut out, Token::Semicolon, at, chars.pos());
+                        }
+                        '&' => {
+                            buf.flush(&mut out, at);
+                            if let Some('&') = chars.peek() {
                                 chars.next();
-                                out.push(Token::RedirectOutAppend);
+                                push(&mut out, Token::AndIf, at, chars.pos());
                             } else {
-                                out.push(Token::RedirectOut);
+                                push(&mut out, Token::Ampersand, at, chars.pos());
                             }
                         }
-                        '<' => {
-                            flush(&mut out, &mut buf);
-                            out.push(Token::RedirectIn);
+                        '|' => {
+                            buf.flush(&mut out, at);
+                            if let Some('|') = chars.peek() {
+                                chars.next();
+                                push(&mut out, Token::OrIf, at, chars.pos());
+                            } else {
+                                push(&mut out, Token::Pipe, at, chars.pos());
+                            }
+                        }
+                        // `name=(...)` assigns an array; the items are
+                        // kept as written for the parser to split.
+                        '(' if buf.is_array_assignment() => {
+                            let body = Self::until_close_paren(&mut chars, at)?;
+                            for c in format!("({})", body).chars() {
+                                buf.push_literal(c);
+                            }
+                        }
+                        '(' => {
+                            buf.flush(&mut out, at);
+                            push(&mut out, Token::LParen, at, chars.pos());
+                        }
+                        ')' => {
+                            buf.flush(&mut out, at);
+                            push(&mut out, Token::RParen, at, chars.pos());
+                        }
+                        '\n' => {
+                            buf.flush(&mut out, at);
+                            push(&mut out, Token::Newline, at, chars.pos());
+                        }
+                        c if c.is_whitespace() => buf.flush(&mut out, at),
+                        c => {
+                            buf.begin(at);
+                            buf.push_literal(c);
                         }
-                        c if c.is_whitespace() => flush(&mut out, &mut buf),
-                        c => buf.push(c),
                     }
                 }
                 Mode::InSingle => match ch {
                     '\'' => mode = Mode::Normal,
-                    c => buf.push(c),
+                    c => buf.push_quoted(c),
                 },
                 Mode::InDouble => match ch {
                     '"' => mode = Mode::Normal,
                     '\\' => {
                         if let Some(n) = chars.next() {
-                            let push: Cow<'static, str> = match n {
-                                '"' => "\"".into(),
-                                '`' => "`".into(),
-                                '\\' => "\\".into(),
-                                '$' => "$".into(),
+                            match n {
+                                '"' | '`' | '\\' | '$' => buf.push_quoted(n),
+                                '\n' => {}
                                 other => {
-                                    buf.push('\\');
-                                    buf.push(other);
-                                    continue;
+                                    buf.push_quoted('\\');
+                                    buf.push_quoted(other);
                                 }
-                            };
-                            buf.push_str(&push);
+                            }
                         };
                     }
-                    c => buf.push(c),
+                    '$' => match Self::dollar(&mut chars, true, at)? {
+                        Some(part) => buf.parts.push(part),
+                        None => buf.push_quoted('$'),
+                    },
+                    '`' => buf.parts.push(Self::backquote(&mut chars, true, at)?),
+                    c => buf.push_quoted(c),
                 },
             }
         }
 
         if mode != Mode::Normal {
-            return Err("unmatched quote".into());
-        }
-        if !buf.is_empty() {
-            out.push(Token::Word(buf));
+            return Err(TokenizeError::UnmatchedQuote {
+                quote: if mode == Mode::InSingle { '\'' } else { '"' },
+                span: Span::new(quote_start, line.len()),
+            });
         }
+        buf.flush(&mut out, line.len());
         Ok(out)
     }
+
+    /// Reads the operand of a `${name op word}` expansion as a word in which
+    /// blanks are ordinary characters. Inside double quotes (`quoted`) the
+    /// whole operand is quoted, single quotes included.
+    pub fn operand(text: &str, quoted: bool) -> Result<Word, TokenizeError> {
+        let mut buf = WordBuilder::default();
+        let mut chars = Cursor {
+            chars: text.char_indices().peekable(),
+            len: text.len(),
+        };
+        let mut quote: Option<(char, usize)> = None;
+        loop {
+            let at = chars.pos();
+            let Some(ch) = chars.next() else {
+                break;
+            };
+            let in_double = quoted || quote.is_some();
+            match (quote, ch) {
+                (Some(('\'', _)), '\'') | (Some(('"', _)), '"') => quote = None,
+                (Some(('\'', _)), c) => buf.push_quoted(c),
+                (None, '\'' | '"') if !quoted || ch == '"' => {
+                    quote = Some((ch, at));
+                    buf.parts.push(WordPart::Quoted(String::new()));
+                }
+                (_, '\\') => match chars.next() {
+                    Some(n) if !in_double || "\"`\\$}".contains(n) => buf.push_quoted(n),
+                    Some(n) => {
+                        buf.push_quoted('\\');
+                        buf.push_quoted(n);
+                    }
+                    None => buf.push_literal('\\'),
+                },
+                (_, '$') => match Self::dollar(&mut chars, in_double, at)? {
+                    Some(part) => buf.parts.push(part),
+                    None if in_double => buf.push_quoted('$'),
+                    None => buf.push_literal('$'),
+                },
+                (_, '`') => buf.parts.push(Self::backquote(&mut chars, in_double, at)?),
+                (_, c) if in_double => buf.push_quoted(c),
+                (_, c) => buf.push_literal(c),
+            }
+        }
+        if let Some((q, at)) = quote {
+            return Err(TokenizeError::UnmatchedQuote {
+                quote: q,
+                span: Span::new(at, text.len()),
+            });
+        }
+        Ok(Word { parts: buf.parts })
+    }
+
+    /// Reads the expansion following a `$` at byte `at`, or returns `None`
+    /// when the `$` is literal.
+    fn dollar(
+        chars: &mut Cursor,
+        quoted: bool,
+        at: usize,
+    ) -> Result<Option<WordPart>, TokenizeError> {
+        let part = match chars.peek() {
+            Some('(') => {
+                chars.next();
+                WordPart::CommandSub {
+                    command: Self::until_close_paren(chars, at)?,
+                    quoted,
+                }
+            }
+            Some('{') => {
+                chars.next();
+                let mut depth = 1;
+                let mut name = String::new();
+                loop {
+                    let c = chars.next().ok_or(TokenizeError::BadSubstitution {
+                        span: Span::new(at, chars.len),
+                    })?;
+                    match c {
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    name.push(c);
+                }
+                WordPart::Param { name, quoted }
+            }
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = String::new();
+                while let Some(c) = chars.peek() {
+                    if c != '_' && !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                WordPart::Param { name, quoted }
+            }
+            Some(c) if c.is_ascii_digit() || "?$-!#@*".contains(c) => {
+                chars.next();
+                WordPart::Param {
+                    name: c.to_string(),
+                    quoted,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(part))
+    }
+
+    /// Reads the body of `$( ... )` up to its matching close paren.
+    fn until_close_paren(chars: &mut Cursor, at: usize) -> Result<String, TokenizeError> {
+        let mut depth = 1;
+        let mut body = String::new();
+        let mut quote: Option<char> = None;
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some('"'), '\\') | (None, '\\') => {
+                    body.push(c);
+                    if let Some(n) = chars.next() {
+                        body.push(n);
+                    }
+                    continue;
+                }
+                (Some(_), _) => {}
+                (None, '\'') | (None, '"') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(body);
+                    }
+                }
+                _ => {}
+            }
+            body.push(c);
+        }
+        Err(TokenizeError::UnterminatedSubstitution {
+            span: Span::new(at, chars.len),
+        })
+    }
+
+    /// Reads a `` `cmd` `` substitution, where a backslash only escapes
+    /// `$`, `` ` `` and `\`.
+    fn backquote(chars: &mut Cursor, quoted: bool, at: usize) -> Result<WordPart, TokenizeError> {
+        let mut command = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '`' => return Ok(WordPart::CommandSub { command, quoted }),
+                '\\' => match chars.next() {
+                    Some(n @ ('$' | '`' | '\\')) => command.push(n),
+                    Some(n) => {
+                        command.push('\\');
+                        command.push(n);
+                    }
+                    None => break,
+                },
+                c => command.push(c),
+            }
+        }
+        Err(TokenizeError::UnterminatedSubstitution {
+            span: Span::new(at, chars.len),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -187,4 +599,93 @@ mod tests {
             ]
         );
     }
+    #[test]
+    fn expansions_keep_their_quoting() {
+        assert_eq!(
+            ArgvTokenizer::tokenize("echo \"$HOME/x\" '*.rs' a$(ls \")\")b ''").unwrap(),
+            vec![
+                Token::Word(String::from("echo")),
+                Token::Compound(Word {
+                    parts: vec![
+                        WordPart::Param {
+                            name: String::from("HOME"),
+                            quoted: true
+                        },
+                        WordPart::Quoted(String::from("/x")),
+                    ]
+                }),
+                Token::Compound(Word {
+                    parts: vec![WordPart::Quoted(String::from("*.rs"))]
+                }),
+                Token::Compound(Word {
+                    parts: vec![
+                        WordPart::Literal(String::from("a")),
+                        WordPart::CommandSub {
+                            command: String::from("ls \")\""),
+                            quoted: false
+                        },
+                        WordPart::Literal(String::from("b")),
+                    ]
+                }),
+                Token::Compound(Word {
+                    parts: vec![WordPart::Quoted(String::new())]
+                }),
+            ]
+        );
+    }
+    #[test]
+    fn comments_and_spans() {
+        let tokens = ArgvTokenizer::tokenize_spanned("ls 'a b' # note\n").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                SpannedToken {
+                    token: Token::Word(String::from("ls")),
+                    span: Span::new(0, 2)
+                },
+                SpannedToken {
+                    token: Token::Word(String::from("a b")),
+                    span: Span::new(3, 8)
+                },
+                SpannedToken {
+                    token: Token::Newline,
+                    span: Span::new(15, 16)
+                },
+            ]
+        );
+        assert_eq!(
+            ArgvTokenizer::tokenize("echo \"abc").unwrap_err(),
+            TokenizeError::UnmatchedQuote {
+                quote: '"',
+                span: Span::new(5, 9)
+            }
+        );
+    }
+    #[test]
+    fn operators() {
+        assert_eq!(
+            ArgvTokenizer::tokenize("(cd x; ls) && echo a||b | wc >| f 2>|g &\n").unwrap(),
+            vec![
+                Token::LParen,
+                Token::Word(String::from("cd")),
+                Token::Word(String::from("x")),
+                Token::Semicolon,
+                Token::Word(String::from("ls")),
+                Token::RParen,
+                Token::AndIf,
+                Token::Word(String::from("echo")),
+                Token::Word(String::from("a")),
+                Token::OrIf,
+                Token::Word(String::from("b")),
+                Token::Pipe,
+                Token::Word(String::from("wc")),
+                Token::RedirectClobber,
+                Token::Word(String::from("f")),
+                Token::RedirectErrClobber,
+                Token::Word(String::from("g")),
+                Token::Ampersand,
+                Token::Newline,
+            ]
+        );
+    }
 }
@@ -0,0 +1,85 @@
+use std::fmt;
+
+/// Characters whose meaning changes when they are quoted, so quoted text
+/// containing them must keep its quoting information. Besides the pattern
+/// and keyword characters this covers those special in the regular
+/// expressions of `[[ =~ ]]`.
+const QUOTE_SENSITIVE: &str = "*?[]~={}!.^$+|()\\";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WordPart {
+    /// Unquoted text, subject to tilde and pathname expansion.
+    Literal(String),
+    /// Text from single quotes, double quotes or a backslash escape.
+    Quoted(String),
+    /// `$name` or `${name}`.
+    Param { name: String, quoted: bool },
+    /// `$(cmd)` or `` `cmd` ``.
+    CommandSub { command: String, quoted: bool },
+}
+
+/// A shell word before expansion.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+impl Word {
+    pub fn literal(s: &str) -> Self {
+        Word {
+            parts: vec![WordPart::Literal(s.to_string())],
+        }
+    }
+
+    /// Collapses the word to plain text when quoting makes no difference to
+    /// how it expands, which is the case for most words. Quoting before the
+    /// first unquoted `=` is kept, since it stops the word being taken as an
+    /// assignment.
+    pub fn simplify(self) -> Result<String, Word> {
+        let mut text = String::new();
+        let mut quoted_name = false;
+        for part in &self.parts {
+            match part {
+                WordPart::Literal(s) if quoted_name && s.contains('=') => return Err(self),
+                WordPart::Literal(s) => text.push_str(s),
+                WordPart::Quoted(s) if !s.chars().any(|c| QUOTE_SENSITIVE.contains(c)) => {
+                    quoted_name |= !text.contains('=');
+                    text.push_str(s)
+                }
+                _ => return Err(self),
+            }
+        }
+        if text.is_empty() {
+            return Err(self);
+        }
+        Ok(text)
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            match part {
+                WordPart::Literal(s) => {
+                    for (j, c) in s.chars().enumerate() {
+                        let comment = c == '#' && i == 0 && j == 0;
+                        if comment || " \t\n'\"\\$`;&|<>()".contains(c) {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{}", c)?;
+                    }
+                }
+                WordPart::Quoted(s) => write!(f, "'{}'", s.replace('\'', "'\\''"))?,
+                WordPart::Param { name, quoted } => {
+                    let q = if *quoted { "\"" } else { "" };
+                    write!(f, "{}${{{}}}{}", q, name, q)?
+                }
+                WordPart::CommandSub { command, quoted } => {
+                    let q = if *quoted { "\"" } else { "" };
+                    write!(f, "{}$({}){}", q, command, q)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use crate::cli::command::CommandContext;
+use crate::cli::registry::CommandRegistry;
+use crate::utils::{git, sys};
+use std::env;
+use std::path::Path;
+
+pub const DEFAULT_PS1: &str = "$ ";
+pub const DEFAULT_PS2: &str = "> ";
+
+// Readline's markers for the start and end of a non-printing sequence.
+// Terminals ignore them, while line editors use them to measure the
+// visible width of the prompt.
+const NON_PRINTING_START: char = '\x01';
+const NON_PRINTING_END: char = '\x02';
+
+/// Renders a `PS1`/`PS2` style prompt.
+///
+/// Supports the bash backslash escapes (`\u`, `\h`, `\H`, `\w`, `\W`, `\$`,
+/// `\t`, `\T`, `\d`, `\j`, `\?`, `\s`, `\n`, `\e`, `\a`, `\\`, `\nnn`,
+/// `\[` and `\]`), `\g` for the current git branch, and `$VAR`, `${VAR}`,
+/// `$(cmd)` and `` `cmd` `` substitutions.
+pub fn render(template: &str, ctx: &mut CommandContext, registry: &CommandRegistry) -> String {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                let Some(esc) = chars.next() else {
+                    out.push('\\');
+                    break;
+                };
+                match esc {
+                    'u' => out.push_str(&sys::username()),
+                    'h' => {
+                        let host = sys::hostname();
+                        out.push_str(host.split('.').next().unwrap_or_default());
+                    }
+                    'H' => out.push_str(&sys::hostname()),
+                    'w' => out.push_str(&working_dir(ctx)),
+                    'W' => out.push_str(&working_dir_base(ctx)),
+                    '$' => out.push(if sys::is_root() { '#' } else { '$' }),
+                    't' => out.push_str(&sys::local_time("%H:%M:%S")),
+                    'T' => out.push_str(&sys::local_time("%I:%M:%S")),
+                    'd' => out.push_str(&sys::local_time("%a %b %d")),
+                    'j' => out.push_str(&ctx.jobs.len().to_string()),
+                    '?' => out.push_str(&ctx.last_status.to_string()),
+                    's' => out.push_str(&shell_name()),
+                    'g' => {
+                        if let Some(branch) = env::current_dir()
+                            .ok()
+                            .and_then(|d| git::current_branch(&d))
+                        {
+                            out.push_str(&branch);
+                        }
+                    }
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    'a' => out.push('\x07'),
+                    'e' => out.push('\x1b'),
+                    '[' => out.push(NON_PRINTING_START),
+                    ']' => out.push(NON_PRINTING_END),
+                    '\\' => out.push('\\'),
+                    '0'..='7' => {
+                        let mut value = esc.to_digit(8).unwrap();
+                        for _ in 0..2 {
+                            match chars.peek().and_then(|c| c.to_digit(8)) {
+                                Some(d) => {
+                                    value = value * 8 + d;
+                                    chars.next();
+                                }
+                                None => break,
+                            }
+                        }
+                        if let Some(c) = char::from_u32(value) {
+                            out.push(c);
+                        }
+                    }
+                    other => {
+                        out.push('\\');
+                        out.push(other);
+                    }
+                }
+            }
+            '$' => match chars.peek().copied() {
+                Some('(') => {
+                    chars.next();
+                    let mut depth = 1;
+                    let mut cmd = String::new();
+                    for c in chars.by_ref() {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                        cmd.push(c);
+                    }
+                    out.push_str(&registry.capture_output(&cmd, ctx));
+                }
+                Some('{') => {
+                    chars.next();
+                    let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    out.push_str(&ctx.get_var(&name).unwrap_or_default());
+                }
+                Some(c) if c == '?' || c == '$' => {
+                    chars.next();
+                    out.push_str(&ctx.get_var(&c.to_string()).unwrap_or_default());
+                }
+                Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                    let mut name = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c != '_' && !c.is_ascii_alphanumeric() {
+                            break;
+                        }
+                        name.push(c);
+                        chars.next();
+                    }
+                    out.push_str(&ctx.get_var(&name).unwrap_or_default());
+                }
+                _ => out.push('$'),
+            },
+            '`' => {
+                let cmd: String = chars.by_ref().take_while(|&c| c != '`').collect();
+                out.push_str(&registry.capture_output(&cmd, ctx));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The current directory with `$HOME` abbreviated to `~`, as for `\w`.
+fn working_dir(ctx: &CommandContext) -> String {
+    let pwd = ctx
+        .get_var("PWD")
+        .or_else(|| env::current_dir().ok().map(|d| d.display().to_string()))
+        .unwrap_or_default();
+    match ctx.get_var("HOME") {
+        Some(home) if !home.is_empty() && home != "/" => match pwd.strip_prefix(&home) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("~{}", rest),
+            _ => pwd,
+        },
+        _ => pwd,
+    }
+}
+
+/// The basename of the current directory, as for `\W`.
+fn working_dir_base(ctx: &CommandContext) -> String {
+    let dir = working_dir(ctx);
+    if dir == "~" || dir == "/" {
+        return dir;
+    }
+    Path::new(&dir)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(dir)
+}
+
+fn shell_name() -> String {
+    env::args()
+        .next()
+        .and_then(|arg0| {
+            Path::new(&arg0)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::command::IoHandle;
+    use crate::cli::dirs::DirStack;
+    use crate::cli::hash::HashTable;
+    use crate::cli::jobs::JobTable;
+    use crate::cli::options::ShellOptions;
+    use crate::cli::params::Params;
+    use crate::cli::traps::TrapTable;
+
+    fn context(vars: &[(&str, &str)]) -> CommandContext {
+        CommandContext {
+            stdin: IoHandle::Stdin,
+            stdout: IoHandle::Stdout,
+            stderr: IoHandle::Stderr,
+            vars: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            params: Params::default(),
+            last_status: 0,
+            jobs: JobTable::default(),
+            options: ShellOptions::default(),
+            interactive: false,
+            errexit_ignored: 0,
+            hash: HashTable::default(),
+            dir_stack: DirStack::default(),
+            traps: TrapTable::default(),
+        }
+    }
+
+    #[test]
+    fn working_dir_escapes() {
+        let registry = CommandRegistry::new();
+        let mut ctx = context(&[("HOME", "/home/me"), ("PWD", "/home/me/src/shell")]);
+        assert_eq!(render("\\w \\W", &mut ctx, &registry), "~/src/shell shell");
+
+        let mut ctx = context(&[("HOME", "/home/me"), ("PWD", "/home/meta")]);
+        assert_eq!(render("\\w", &mut ctx, &registry), "/home/meta");
+
+        let mut ctx = context(&[("HOME", "/home/me"), ("PWD", "/home/me")]);
+        assert_eq!(render("\\W", &mut ctx, &registry), "~");
+    }
+
+    #[test]
+    fn status_and_parameters() {
+        let registry = CommandRegistry::new();
+        let mut ctx = context(&[("NAME", "box")]);
+        ctx.last_status = 3;
+        assert_eq!(
+            render("[\\?] $NAME ${NAME}:$? $ ", &mut ctx, &registry),
+            "[3] box box:3 $ "
+        );
+    }
+
+    #[test]
+    fn non_printing_and_octal() {
+        let registry = CommandRegistry::new();
+        let mut ctx = context(&[]);
+        assert_eq!(
+            render("\\[\\e[32m\\]ok\\[\\033[0m\\]\\\\", &mut ctx, &registry),
+            "\x01\x1b[32m\x02ok\x01\x1b[0m\x02\\"
+        );
+    }
+}
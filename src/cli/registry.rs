@@ -1,18 +1,98 @@
-use crate::cli::command::{Command, CommandContext, IoHandle};
-use crate::cli::parser::parse_simple::{parse_command, ParsedCommand, RedirKind};
-use crate::cli::parser::tokenize::ArgvTokenizer;
-use crate::utils::path_lookup::find_in_path;
-use std::cell::RefCell;
+use crate::cli::command::{Argument, Command, CommandContext, IoHandle};
+use crate::cli::cond;
+use crate::cli::diagnostic::{self, Origin};
+use crate::cli::error::{final_status, ExitStatus, ShellError};
+use crate::cli::expand::{expand_plain, expand_single, expand_words};
+use crate::cli::parser::parse_list::{
+    parse_source, AndOrList, CommandNode, Connector, List, Pipeline,
+};
+use crate::cli::parser::parse_simple::{
+    assignment_of, AssignedValue, Assignment, ParsedCommand, RedirKind, Redirection,
+};
+use crate::cli::parser::word::{Word, WordPart};
+use crate::cli::prompt;
+use crate::cli::traps::Condition;
+use crate::cli::vars::Variable;
+use crate::utils::process::{self, Fork};
+use crate::utils::quote::shell_quote;
+use crate::utils::signal;
+use crate::utils::sys;
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
 use std::process::Command as ProcCommand;
 use std::process::Stdio;
 use std::rc::Rc;
 
+/// Builtins whose `name=value` arguments are expanded as assignments.
+const DECLARATIONS: &[&str] = &["declare", "typeset", "readonly", "local", "export"];
+
+/// The value of an assignment once expanded.
+enum Expanded {
+    Scalar(String),
+    /// Items with their subscripts, if given.
+    Array(Vec<(Option<String>, String)>),
+}
+
+/// Writes an expanded assignment back out as one word. Array items are
+/// always quoted; a plain value only if `quote` is set, as for `set -x`.
+fn assignment_text(
+    a: &Assignment,
+    subscript: Option<&str>,
+    value: &Expanded,
+    quote: bool,
+) -> String {
+    let mut text = a.name.clone();
+    if let Some(sub) = subscript {
+        text.push_str(&format!("[{}]", sub));
+    }
+    text.push_str(if a.append { "+=" } else { "=" });
+    match value {
+        Expanded::Scalar(value) if quote => text.push_str(&shell_quote(value)),
+        Expanded::Scalar(value) => text.push_str(value),
+        Expanded::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|(sub, value)| match sub {
+                    Some(sub) => format!("[{}]={}", shell_quote(sub), shell_quote(value)),
+                    None => shell_quote(value),
+                })
+                .collect();
+            text.push_str(&format!("({})", items.join(" ")));
+        }
+    }
+    text
+}
+
+#[derive(Default)]
 pub struct CommandRegistry {
     commands: HashMap<String, Rc<dyn Command>>,
 }
 
+/// Handles displaced by redirections, restored once the command finishes.
+#[derive(Default)]
+struct SavedIo {
+    stdin: Option<IoHandle>,
+    stdout: Option<IoHandle>,
+    stderr: Option<IoHandle>,
+}
+
+impl SavedIo {
+    fn restore(self, ctx: &mut CommandContext) {
+        if let Some(x) = self.stdin {
+            ctx.stdin = x;
+        }
+        if let Some(x) = self.stdout {
+            ctx.stdout = x;
+        }
+        if let Some(x) = self.stderr {
+            ctx.stderr = x;
+        }
+    }
+}
+
 impl CommandRegistry {
     pub fn new() -> Self {
         Self {
@@ -28,142 +108,777 @@ impl CommandRegistry {
         self.commands.get(name)
     }
 
-    pub fn execute_parsed(&self, parsed: ParsedCommand, ctx: &mut CommandContext) -> bool {
-        let mut old_stdout: Option<IoHandle> = None;
-        let mut old_stdin: Option<IoHandle> = None;
-        let mut old_stderr: Option<IoHandle> = None;
+    fn apply_redirects(
+        &self,
+        redirects: &[Redirection],
+        ctx: &mut CommandContext,
+    ) -> Result<SavedIo, String> {
+        let targets = redirects
+            .iter()
+            .map(|redir| expand_single(&redir.target, ctx, self))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut saved = SavedIo::default();
 
-        for redir in &parsed.redirects {
+        for (redir, target) in redirects.iter().zip(&targets) {
+            let file = match open_target(&redir.kind, target, ctx.options.noclobber) {
+                Ok(file) => file,
+                Err(e) => {
+                    // undo the redirections already made
+                    saved.restore(ctx);
+                    return Err(e);
+                }
+            };
+            let handle = IoHandle::file(file);
             match redir.kind {
-                RedirKind::StdoutTruncate => {
-                    let file = File::create(&redir.target).unwrap();
-                    old_stdout = Some(std::mem::replace(
-                        &mut ctx.stdout,
-                        IoHandle::File(Rc::new(RefCell::new(file))),
-                    ));
-                }
-                RedirKind::StdoutAppend => {
-                    let file = OpenOptions::new()
-                        .append(true)
-                        .create(true)
-                        .open(&redir.target)
-                        .unwrap();
-                    old_stdout = Some(std::mem::replace(
-                        &mut ctx.stdout,
-                        IoHandle::File(Rc::new(RefCell::new(file))),
-                    ));
-                }
-                RedirKind::StderrTruncate => {
-                    let file = File::create(&redir.target).unwrap();
-                    old_stderr = Some(std::mem::replace(
-                        &mut ctx.stderr,
-                        IoHandle::File(Rc::new(RefCell::new(file))),
-                    ));
-                }
-                RedirKind::StderrAppend => {
-                    let file = OpenOptions::new()
-                        .append(true)
-                        .create(true)
-                        .open(&redir.target)
-                        .unwrap();
-                    old_stderr = Some(std::mem::replace(
-                        &mut ctx.stderr,
-                        IoHandle::File(Rc::new(RefCell::new(file))),
-                    ));
+                RedirKind::StdoutTruncate | RedirKind::StdoutAppend | RedirKind::StdoutClobber => {
+                    let old = std::mem::replace(&mut ctx.stdout, handle);
+                    saved.stdout.get_or_insert(old);
+                }
+                RedirKind::StderrTruncate | RedirKind::StderrAppend | RedirKind::StderrClobber => {
+                    let old = std::mem::replace(&mut ctx.stderr, handle);
+                    saved.stderr.get_or_insert(old);
                 }
                 RedirKind::Stdin => {
-                    let file = File::open(&redir.target).unwrap();
-                    old_stdin = Some(std::mem::replace(
-                        &mut ctx.stdin,
-                        IoHandle::File(Rc::new(RefCell::new(file))),
-                    ));
+                    let old = std::mem::replace(&mut ctx.stdin, handle);
+                    saved.stdin.get_or_insert(old);
                 }
             }
         }
-        let cmd_name = &parsed.argv[0];
-        let args: Vec<&str> = parsed.argv.iter().skip(1).map(|s| s.as_str()).collect();
-        if let Some(cmd) = self.commands.get(cmd_name) {
-            cmd.execute(&args, ctx);
-        } else if let Some(_) = find_in_path(cmd_name) {
-            let mut cmd = ProcCommand::new(cmd_name);
-            cmd.args(&args);
+        Ok(saved)
+    }
+
+    /// Prints the expanded command to stderr, prefixed with `PS4`, for
+    /// `set -x`.
+    fn trace(&self, assigned: &[String], argv: &[String], ctx: &mut CommandContext) {
+        let ps4 = ctx.get_var("PS4").unwrap_or_else(|| "+ ".into());
+        let words: Vec<String> = assigned
+            .iter()
+            .cloned()
+            .chain(argv.iter().map(|w| shell_quote(w)))
+            .collect();
+        let line = format!("{}{}", prompt::render(&ps4, ctx, self), words.join(" "));
+        ctx.stderr.write_line(&line).ok();
+    }
 
-            match ctx.stdin {
-                IoHandle::Stdin => cmd.stdin(Stdio::inherit()),
-                IoHandle::File(ref file) => {
-                    let _file = file.borrow();
-                    cmd.stdin(Stdio::from(_file.try_clone().unwrap()))
+    /// Expands the words of a command. The assignments given to a
+    /// declaration builtin, run directly or through `builtin` or `command`,
+    /// are expanded as assignments are, each to one argument, with the items
+    /// of `name=(...)` quoted for the builtin to split again.
+    fn expand_argv(
+        &self,
+        words: &[Word],
+        ctx: &mut CommandContext,
+    ) -> Result<Vec<Argument>, String> {
+        let declaration = words
+            .iter()
+            .map(|word| match word.parts.as_slice() {
+                [WordPart::Literal(name)] => Some(name.as_str()),
+                _ => None,
+            })
+            .find(|name| !matches!(name, Some("builtin" | "command")))
+            .flatten()
+            .is_some_and(|name| DECLARATIONS.contains(&name));
+        if !declaration {
+            let fields = expand_words(words, ctx, self)?;
+            return Ok(fields.into_iter().map(Argument::from).collect());
+        }
+        let mut argv = vec![];
+        for word in words {
+            match assignment_of(word) {
+                Ok(Some(a)) => {
+                    let (subscript, value) = self.expand_assignment(&a, ctx)?;
+                    argv.push(Argument {
+                        text: assignment_text(&a, subscript.as_deref(), &value, false),
+                        array: matches!(value, Expanded::Array(_)),
+                    });
                 }
-                _ => unreachable!("wrong assignment"),
-            };
+                _ => {
+                    let fields = expand_words(std::slice::from_ref(word), ctx, self)?;
+                    argv.extend(fields.into_iter().map(Argument::from));
+                }
+            }
+        }
+        Ok(argv)
+    }
 
-            match ctx.stdout {
-                IoHandle::Stdout => cmd.stdout(Stdio::inherit()),
-                IoHandle::File(ref file) => {
-                    let _file = file.borrow();
-                    cmd.stdout(Stdio::from(_file.try_clone().unwrap()))
+    /// Expands the subscript and value of an assignment. Array items
+    /// without a subscript are split into fields like command arguments.
+    fn expand_assignment(
+        &self,
+        a: &Assignment,
+        ctx: &mut CommandContext,
+    ) -> Result<(Option<String>, Expanded), String> {
+        let subscript = match &a.subscript {
+            Some(word) => Some(expand_plain(word, ctx, self)?),
+            None => None,
+        };
+        let value = match &a.value {
+            AssignedValue::Scalar(word) => Expanded::Scalar(expand_plain(word, ctx, self)?),
+            AssignedValue::Array(items) => {
+                let mut values = vec![];
+                for item in items {
+                    match &item.subscript {
+                        Some(sub) => {
+                            let sub = expand_plain(sub, ctx, self)?;
+                            values.push((Some(sub), expand_plain(&item.value, ctx, self)?));
+                        }
+                        None => {
+                            let fields =
+                                expand_words(std::slice::from_ref(&item.value), ctx, self)?;
+                            values.extend(fields.into_iter().map(|v| (None, v)));
+                        }
+                    }
                 }
-                _ => unreachable!("wrong assignment"),
-            };
+                Expanded::Array(values)
+            }
+        };
+        Ok((subscript, value))
+    }
 
-            match ctx.stderr {
-                IoHandle::Stderr => cmd.stderr(Stdio::inherit()),
-                IoHandle::File(ref file) => {
-                    let _file = file.borrow();
-                    cmd.stderr(Stdio::from(_file.try_clone().unwrap()))
+    pub fn execute_parsed(
+        &self,
+        parsed: &ParsedCommand,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let args = match self.expand_argv(&parsed.argv, ctx) {
+            Ok(v) => v,
+            Err(e) => return Self::expansion_failed(&e, ctx),
+        };
+        let argv: Vec<String> = args.iter().map(|a| a.text.clone()).collect();
+        let saved = match self.apply_redirects(&parsed.redirects, ctx) {
+            Ok(saved) => saved,
+            Err(e) => {
+                ctx.stderr.write_line(format!("shell: {}", e).as_str()).ok();
+                ctx.last_status = 1;
+                return Ok(1);
+            }
+        };
+
+        // Each assignment is expanded once those before it have been made.
+        // Before a command they last only as long as it does.
+        let permanent = argv.is_empty();
+        let mut assigned = vec![];
+        let mut displaced = vec![];
+        let mut failed = false;
+        for a in &parsed.assignments {
+            let (subscript, value) = match self.expand_assignment(a, ctx) {
+                Ok(expanded) => expanded,
+                Err(e) => {
+                    Self::undo_assignments(displaced, ctx);
+                    saved.restore(ctx);
+                    return Self::expansion_failed(&e, ctx);
                 }
-                _ => unreachable!("wrong assignment"),
             };
+            let old = ctx.vars.lookup(&a.name).cloned();
+            let text = assignment_text(a, subscript.as_deref(), &value, true);
+            let result = match value {
+                Expanded::Scalar(value) => {
+                    ctx.vars
+                        .assign(&a.name, subscript.as_deref(), value, a.append)
+                }
+                Expanded::Array(items) => ctx.vars.assign_array(&a.name, items, a.append),
+            };
+            if let Err(e) = result {
+                ctx.stderr.write_line(&format!("shell: {}", e)).ok();
+                failed = true;
+                break;
+            }
+            if !permanent {
+                ctx.vars.export(&a.name, true);
+                displaced.push((a.name.clone(), old));
+            }
+            assigned.push(text);
+        }
+        if ctx.options.xtrace && !(argv.is_empty() && assigned.is_empty()) {
+            self.trace(&assigned, &argv, ctx);
+        }
 
-            match cmd.spawn().and_then(|mut child| child.wait()) {
-                Ok(_status) => {
-                    //writeln!(ctx.stdout, "{}", status).ok();
+        let result = match argv.first() {
+            // A failed assignment on its own line aborts a non-interactive
+            // shell, as a failed expansion does.
+            _ if failed => {
+                ctx.last_status = 1;
+                if permanent && !ctx.interactive {
+                    Err(ShellError::exit(1))
+                } else {
+                    Ok(1)
                 }
-                Err(e) => {
+            }
+            // Redirections alone still create or truncate their targets. A
+            // word that expanded to nothing keeps the status of any command
+            // substitution it ran, as does an assignment.
+            None => {
+                let substituted = parsed.assignments.iter().any(|a| a.substitutes());
+                if parsed.argv.is_empty() && !substituted {
+                    ctx.last_status = 0;
+                }
+                Ok(ctx.last_status)
+            }
+            Some(cmd_name) => {
+                if let Some(cmd) = self.commands.get(cmd_name) {
+                    Self::run_builtin(cmd, &args, ctx)
+                } else if let Some(path) = ctx.hash.lookup(cmd_name, &ctx.path_var()) {
+                    Self::run_external(&path, &argv, ctx).or_else(|e| {
+                        ctx.stderr.write_line(&format!("shell: {}", e)).ok();
+                        ctx.last_status = e.status();
+                        Ok(ctx.last_status)
+                    })
+                } else {
                     ctx.stderr
-                        .write_line(format!("Failed to run {}: {}", cmd_name, e).as_str())
+                        .write_line(format!("{}: not found", cmd_name).as_str())
                         .ok();
+                    ctx.last_status = 127;
+                    Ok(127)
                 }
             }
-        } else {
-            ctx.stderr
-                .write_line(format!("{}: not found", cmd_name).as_str())
-                .ok();
-        }
-        if let Some(x) = old_stdin {
-            ctx.stdin = x;
+        };
+        Self::undo_assignments(displaced, ctx);
+        saved.restore(ctx);
+        if let Some(last) = argv.last() {
+            ctx.params.last_arg = last.clone();
         }
-        if let Some(x) = old_stdout {
-            ctx.stdout = x;
+        result
+    }
+
+    /// Reports a failed expansion, which aborts a non-interactive shell.
+    fn expansion_failed(e: &str, ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        ctx.stderr.write_line(format!("shell: {}", e).as_str()).ok();
+        ctx.last_status = 1;
+        if !ctx.interactive {
+            return Err(ShellError::exit(1));
         }
-        if let Some(x) = old_stderr {
-            ctx.stderr = x;
+        Ok(1)
+    }
+
+    /// Restores the variables displaced by assignments before a command.
+    fn undo_assignments(displaced: Vec<(String, Option<Variable>)>, ctx: &mut CommandContext) {
+        for (name, old) in displaced.into_iter().rev() {
+            ctx.vars.restore(&name, old);
         }
-        true
     }
 
-    pub fn execute(&self, input: &str, ctx: &mut CommandContext) -> bool {
-        let tokens = match ArgvTokenizer::tokenize(input) {
-            Ok(v) => v,
+    /// All registered commands, sorted by name.
+    pub fn commands(&self) -> Vec<&Rc<dyn Command>> {
+        let mut cmds: Vec<_> = self.commands.values().collect();
+        cmds.sort_by_key(|cmd| cmd.name());
+        cmds
+    }
+
+    /// Runs a builtin with `argv[0]` as its name, flushing its output when it
+    /// returns and reporting any failure. A lone `--help` argument prints the
+    /// builtin's help instead, unless it takes `--help` as an operand. Only
+    /// control flow signals come back as errors.
+    pub fn run_builtin(
+        cmd: &Rc<dyn Command>,
+        argv: &[Argument],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let args = &argv[1..];
+        let result = if args.len() == 1 && args[0].text == "--help" && cmd.accepts_help_flag() {
+            let text = help_text(cmd.as_ref()) + "\n";
+            ctx.write_stdout(text.as_bytes()).map(|_| 0)
+        } else {
+            cmd.execute_args(args, ctx)
+        };
+        let result = match ctx.stdout.flush() {
+            Err(e) if result.is_ok() => Err(ShellError::Write(e)),
+            _ => result,
+        };
+        let status = match result {
+            Ok(status) => status,
+            Err(e @ ShellError::Control(_)) => return Err(e),
+            Err(e) => {
+                let msg = format!("{}: {}", argv[0].text, e);
+                ctx.stderr.write_line(&msg).ok();
+                e.status()
+            }
+        };
+        ctx.last_status = status;
+        Ok(status)
+    }
+
+    /// Runs the program at `path` with `argv` and waits for it. Failing to
+    /// hand it the shell's input and output, as when out of descriptors, is
+    /// an error with status 1.
+    pub fn run_external(
+        path: &Path,
+        argv: &[String],
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let spill = ctx.spill();
+        let stdio = stdio_for(&ctx.stdin)
+            .and_then(|stdin| Ok((stdin, stdio_for(&ctx.stdout)?, stdio_for(&ctx.stderr)?)));
+        let (stdin, stdout, stderr) = match stdio {
+            Ok(stdio) => stdio,
+            Err(e) => {
+                spill.collect(ctx);
+                let msg = format!("{}: {}", argv[0], sys::error_message(&e));
+                return Err(ShellError::failed(1, msg));
+            }
+        };
+        let mut cmd = ProcCommand::new(path);
+        cmd.arg0(&argv[0]);
+        cmd.args(&argv[1..]);
+        cmd.env_clear();
+        cmd.envs(ctx.vars.environ());
+        cmd.stdin(stdin);
+        cmd.stdout(stdout);
+        cmd.stderr(stderr);
+
+        match cmd.spawn().and_then(|mut child| child.wait()) {
+            Ok(status) => {
+                ctx.last_status = exit_code(status);
+            }
             Err(e) => {
                 ctx.stderr
-                    .write_line(format!("tokenization error: {}", e).as_str())
+                    .write_line(format!("Failed to run {}: {}", argv[0], e).as_str())
                     .ok();
-                return true;
+                ctx.last_status = 126;
+            }
+        }
+        spill.collect(ctx);
+        Ok(ctx.last_status)
+    }
+
+    pub fn execute_node(
+        &self,
+        node: &CommandNode,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        if let CommandNode::Simple(_) | CommandNode::Conditional(_) = node {
+            if !ctx.traps.running {
+                ctx.vars.set("BASH_COMMAND", node.to_string()).ok();
+                self.run_trap(Condition::Debug, ctx)?;
+            }
+        }
+        match node {
+            CommandNode::Simple(parsed) => self.execute_parsed(parsed, ctx),
+            CommandNode::Group(body, redirects) => {
+                let saved = match self.apply_redirects(redirects, ctx) {
+                    Ok(saved) => saved,
+                    Err(e) => {
+                        ctx.stderr.write_line(format!("shell: {}", e).as_str()).ok();
+                        ctx.last_status = 1;
+                        return Ok(1);
+                    }
+                };
+                let result = self.execute_list(body, ctx);
+                saved.restore(ctx);
+                result
+            }
+            CommandNode::Subshell(body, redirects) => {
+                let spill = ctx.spill();
+                match process::fork() {
+                    Ok(Fork::Child) => {
+                        ctx.traps.enter_subshell();
+                        if let Err(e) = self.apply_redirects(redirects, ctx) {
+                            ctx.stderr.write_line(format!("shell: {}", e).as_str()).ok();
+                            process::exit_child(1);
+                        }
+                        let result = self.execute_list(body, ctx);
+                        self.exit_subshell(result, ctx);
+                    }
+                    Ok(Fork::Parent(pid)) => ctx.last_status = process::wait_pid(pid),
+                    Err(e) => {
+                        ctx.stderr.write_line(format!("fork: {}", e).as_str()).ok();
+                        ctx.last_status = 1;
+                    }
+                }
+                spill.collect(ctx);
+                Ok(ctx.last_status)
             }
+            CommandNode::Conditional(expr) => {
+                if ctx.options.xtrace {
+                    let ps4 = ctx.get_var("PS4").unwrap_or_else(|| "+ ".into());
+                    let line = format!("{}[[ {} ]]", prompt::render(&ps4, ctx, self), expr);
+                    ctx.stderr.write_line(&line).ok();
+                }
+                match cond::evaluate(expr, ctx, self) {
+                    Ok(result) => ctx.last_status = i32::from(!result),
+                    Err(e) => {
+                        ctx.stderr.write_line(format!("shell: {}", e).as_str()).ok();
+                        ctx.last_status = 2;
+                    }
+                }
+                Ok(ctx.last_status)
+            }
+        }
+    }
+
+    pub fn execute_pipeline(
+        &self,
+        pipeline: &Pipeline,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        match pipeline.commands.as_slice() {
+            [single] => self.execute_node(single, ctx)?,
+            commands => self.execute_piped(commands, ctx),
+        };
+        if pipeline.negated {
+            ctx.last_status = i32::from(ctx.last_status == 0);
+        }
+        Ok(ctx.last_status)
+    }
+
+    /// Runs each command of a multi-command pipeline in its own forked child,
+    /// connected by pipes, and waits for all of them.
+    fn execute_piped(&self, commands: &[CommandNode], ctx: &mut CommandContext) -> ExitStatus {
+        let spill = ctx.spill();
+        let mut pids = vec![];
+        let mut prev: Option<File> = None;
+        let mut failed = false;
+
+        for (i, node) in commands.iter().enumerate() {
+            let (reader, writer) = if i + 1 == commands.len() {
+                (None, None)
+            } else {
+                match process::pipe() {
+                    Ok((r, w)) => (Some(r), Some(w)),
+                    Err(e) => {
+                        ctx.stderr.write_line(format!("pipe: {}", e).as_str()).ok();
+                        failed = true;
+                        break;
+                    }
+                }
+            };
+            match process::fork() {
+                Ok(Fork::Child) => {
+                    drop(reader);
+                    ctx.traps.enter_subshell();
+                    if let Some(input) = prev.take() {
+                        ctx.stdin = IoHandle::file(input);
+                    }
+                    if let Some(output) = writer {
+                        ctx.stdout = IoHandle::file(output);
+                    }
+                    let result = self.execute_node(node, ctx);
+                    self.exit_subshell(result, ctx);
+                }
+                Ok(Fork::Parent(pid)) => pids.push(pid),
+                Err(e) => {
+                    ctx.stderr.write_line(format!("fork: {}", e).as_str()).ok();
+                    failed = true;
+                    break;
+                }
+            }
+            prev = reader;
+        }
+        drop(prev);
+
+        let statuses: Vec<i32> = pids.iter().map(|&pid| process::wait_pid(pid)).collect();
+        spill.collect(ctx);
+        ctx.last_status = if failed {
+            1
+        } else if ctx.options.pipefail {
+            statuses
+                .iter()
+                .rev()
+                .copied()
+                .find(|&s| s != 0)
+                .unwrap_or(0)
+        } else {
+            statuses.last().copied().unwrap_or(0)
+        };
+        ctx.last_status
+    }
+
+    pub fn execute_and_or(
+        &self,
+        and_or: &AndOrList,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let pipelines = std::iter::once((None, &and_or.first))
+            .chain(and_or.rest.iter().map(|(c, p)| (Some(*c), p)));
+        let last = and_or.rest.len();
+        for (i, (connector, pipeline)) in pipelines.enumerate() {
+            let run = match connector {
+                None => true,
+                Some(Connector::And) => ctx.last_status == 0,
+                Some(Connector::Or) => ctx.last_status != 0,
+            };
+            if run {
+                self.execute_checked(pipeline, i < last, ctx)?;
+            }
+        }
+        Ok(ctx.last_status)
+    }
+
+    /// Runs a pipeline and applies `set -e` and the `ERR` trap, which do
+    /// not apply to pipelines used as the condition of `&&`/`||` or
+    /// negated with `!`. Traps on signals caught meanwhile run after it.
+    fn execute_checked(
+        &self,
+        pipeline: &Pipeline,
+        condition: bool,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let ignored = condition || pipeline.negated;
+        if ignored {
+            ctx.errexit_ignored += 1;
+        }
+        let result = self.execute_pipeline(pipeline, ctx);
+        if ignored {
+            ctx.errexit_ignored -= 1;
+        }
+        let status = result?;
+        self.run_pending_traps(ctx)?;
+        if status != 0 && ctx.errexit_ignored == 0 && !ignored {
+            self.run_trap(Condition::Err, ctx)?;
+            if ctx.options.errexit {
+                return Err(ShellError::exit(status));
+            }
+        }
+        Ok(status)
+    }
+
+    pub fn execute_list(
+        &self,
+        list: &List,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        for item in &list.items {
+            // `set -n` reads commands without running them, except in an
+            // interactive shell where it could never be turned off again.
+            if ctx.options.noexec && !ctx.interactive {
+                break;
+            }
+            self.run_pending_traps(ctx)?;
+            if item.background {
+                self.spawn_background(&item.and_or, ctx);
+            } else {
+                self.execute_and_or(&item.and_or, ctx)?;
+            }
+        }
+        Ok(ctx.last_status)
+    }
+
+    /// Runs the trap on `cond`, if one is set and no trap is running
+    /// already. `$?` is left as it was unless the trap exits the shell.
+    pub fn run_trap(&self, cond: Condition, ctx: &mut CommandContext) -> Result<(), ShellError> {
+        if ctx.traps.running {
+            return Ok(());
+        }
+        let Some(action) = ctx.traps.get(cond).map(str::to_string) else {
+            return Ok(());
         };
-        let pc = match parse_command(&tokens) {
+        let status = ctx.last_status;
+        ctx.traps.running = true;
+        let result = self.execute(&action, ctx);
+        ctx.traps.running = false;
+        result?;
+        ctx.last_status = status;
+        Ok(())
+    }
+
+    /// Runs the traps on the signals caught since the last call. Signals
+    /// caught while a trap runs wait for the next.
+    pub fn run_pending_traps(&self, ctx: &mut CommandContext) -> Result<(), ShellError> {
+        if ctx.traps.running {
+            return Ok(());
+        }
+        for sig in signal::pending() {
+            self.run_trap(Condition::Signal(sig), ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the `EXIT` trap, once, as the shell or a subshell ends. It
+    /// sees the status the shell is exiting with, which `exit` in the trap
+    /// replaces.
+    pub fn run_exit_trap(&self, ctx: &mut CommandContext) {
+        if let Some(action) = ctx.traps.take(Condition::Exit) {
+            let status = ctx.last_status;
+            ctx.traps.running = true;
+            ctx.last_status = match self.execute(&action, ctx) {
+                Ok(_) => status,
+                Err(e) => e.status(),
+            };
+            ctx.traps.running = false;
+        }
+    }
+
+    /// Ends a forked subshell with the status of `result`.
+    fn exit_subshell(&self, result: Result<ExitStatus, ShellError>, ctx: &mut CommandContext) -> ! {
+        ctx.last_status = final_status(result);
+        self.run_exit_trap(ctx);
+        ctx.stdout.flush().ok();
+        ctx.stderr.flush().ok();
+        process::exit_child(ctx.last_status)
+    }
+
+    /// Runs an and-or list asynchronously in a forked child and records it
+    /// in the job table. The job gets a process group of its own, so that
+    /// signalling it reaches the commands it runs. Anything the job writes
+    /// to an in-memory buffer after it has started is lost.
+    fn spawn_background(&self, and_or: &AndOrList, ctx: &mut CommandContext) {
+        let spill = ctx.spill();
+        match process::fork() {
+            Ok(Fork::Child) => {
+                // Without job control, background jobs must not read the
+                // terminal.
+                ctx.stdin = IoHandle::Null;
+                process::new_process_group(0);
+                ctx.traps.enter_subshell();
+                let result = self.execute_and_or(and_or, ctx);
+                self.exit_subshell(result, ctx);
+            }
+            Ok(Fork::Parent(pid)) => {
+                process::new_process_group(pid);
+                ctx.params.last_background = Some(pid);
+                let job = ctx.jobs.add(pid, and_or.to_string());
+                // As in bash, only an interactive shell announces the job.
+                if ctx.interactive {
+                    let notice = format!("[{}] {}", job.id, job.pid);
+                    ctx.stderr.write_line(&notice).ok();
+                }
+                ctx.last_status = 0;
+            }
+            Err(e) => {
+                ctx.stderr.write_line(format!("fork: {}", e).as_str()).ok();
+                ctx.last_status = 1;
+            }
+        }
+        spill.collect(ctx);
+    }
+
+    pub fn execute(&self, input: &str, ctx: &mut CommandContext) -> Result<ExitStatus, ShellError> {
+        self.execute_at(input, None, ctx)
+    }
+
+    /// Parses and runs `input`, naming `origin` in syntax errors. A syntax
+    /// error sets status 2 and stops a non-interactive shell, as does
+    /// `exit`; both come back as `Control::Exit`.
+    pub fn execute_at(
+        &self,
+        input: &str,
+        origin: Option<Origin>,
+        ctx: &mut CommandContext,
+    ) -> Result<ExitStatus, ShellError> {
+        let list = match parse_source(input) {
             Ok(v) => v,
             Err(e) => {
-                ctx.stderr
-                    .write_line(format!("parse error: {}", e).as_str())
-                    .ok();
-                return true;
+                let text = diagnostic::render(input, e.span(), &e.to_string(), origin);
+                ctx.stderr.write_line(&text).ok();
+                ctx.last_status = 2;
+                if !ctx.interactive {
+                    return Err(ShellError::exit(2));
+                }
+                return Ok(2);
+            }
+        };
+        self.execute_list(&list, ctx)
+    }
+
+    /// Runs `input` in a forked child and returns what it wrote to stdout,
+    /// with trailing newlines removed as in `$(...)`.
+    pub fn capture_output(&self, input: &str, ctx: &mut CommandContext) -> String {
+        let (mut reader, writer) = match process::pipe() {
+            Ok(ends) => ends,
+            Err(_) => return String::new(),
+        };
+        let spill = ctx.spill();
+        let pid = match process::fork() {
+            Ok(Fork::Child) => {
+                drop(reader);
+                ctx.stdin = IoHandle::Null;
+                ctx.stdout = IoHandle::file(writer);
+                ctx.traps.enter_subshell();
+                let result = self.execute(input, ctx);
+                self.exit_subshell(result, ctx);
+            }
+            Ok(Fork::Parent(pid)) => pid,
+            Err(_) => {
+                spill.collect(ctx);
+                return String::new();
             }
         };
-        if pc.argv.is_empty() {
-            return true;
+
+        drop(writer);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).ok();
+        ctx.last_status = process::wait_pid(pid);
+        spill.collect(ctx);
+
+        let mut out = String::from_utf8_lossy(&out).into_owned();
+        while out.ends_with('\n') {
+            out.pop();
         }
-        return self.execute_parsed(pc, ctx);
+        out
+    }
+}
+
+/// The full help for `cmd`, as printed by `help name` and `name --help`.
+pub fn help_text(cmd: &dyn Command) -> String {
+    let mut text = format!(
+        "{}: {}\n    {}.",
+        cmd.name(),
+        cmd.usage(),
+        cmd.description()
+    );
+    if !cmd.help().is_empty() {
+        text.push_str("\n\n");
+        let body: Vec<String> = cmd
+            .help()
+            .lines()
+            .map(|l| {
+                if l.is_empty() {
+                    String::new()
+                } else {
+                    format!("    {}", l)
+                }
+            })
+            .collect();
+        text.push_str(&body.join("\n"));
     }
+    text
+}
+
+/// Opens the file a redirection of `kind` refers to. With `noclobber`, a
+/// plain `>` refuses to truncate an existing regular file, and creates a
+/// new one exclusively so that one made meanwhile is not overwritten.
+/// Other files, such as `/dev/null`, are opened as they are.
+fn open_target(kind: &RedirKind, target: &str, noclobber: bool) -> Result<File, String> {
+    let mut opts = OpenOptions::new();
+    match kind {
+        RedirKind::Stdin => opts.read(true),
+        RedirKind::StdoutAppend | RedirKind::StderrAppend => opts.append(true).create(true),
+        RedirKind::StdoutTruncate | RedirKind::StderrTruncate if noclobber => {
+            match std::fs::metadata(target) {
+                Ok(m) if m.is_file() => return Err(clobber_error(target)),
+                Ok(_) => opts.write(true),
+                Err(_) => opts.write(true).create_new(true),
+            }
+        }
+        _ => opts.write(true).create(true).truncate(true),
+    };
+    opts.open(target).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists if noclobber => clobber_error(target),
+        _ => format!("{}: {}", target, sys::error_message(&e)),
+    })
+}
+
+fn clobber_error(target: &str) -> String {
+    format!("{}: cannot overwrite existing file", target)
+}
+
+fn stdio_for(handle: &IoHandle) -> io::Result<Stdio> {
+    Ok(match handle {
+        IoHandle::Stdin | IoHandle::Stdout | IoHandle::Stderr => Stdio::inherit(),
+        IoHandle::File(file) => {
+            let mut file = file.borrow_mut();
+            file.flush()?;
+            Stdio::from(file.get_ref().try_clone()?)
+        }
+        // Callers spill buffers to files before starting a child.
+        IoHandle::Buffer(_) | IoHandle::Null => Stdio::null(),
+    })
+}
+
+/// Maps a child's exit status to a shell status, using `128 + N` for
+/// children killed by signal N.
+fn exit_code(status: std::process::ExitStatus) -> ExitStatus {
+    status
+        .code()
+        .or_else(|| status.signal().map(|sig| 128 + sig))
+        .unwrap_or(1)
 }
@@ -0,0 +1,143 @@
+use crate::utils::signal::{self, Disposition};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Something a trap can be set on. They are ordered as `trap -p` lists
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Condition {
+    /// The shell exiting.
+    Exit,
+    Signal(libc::c_int),
+    /// Before each simple command.
+    Debug,
+    /// A command failing where `set -e` would exit.
+    Err,
+    /// A function or sourced script returning.
+    Return,
+}
+
+impl Condition {
+    /// Parses a condition by name, in any case and with or without `SIG`,
+    /// or by number, where 0 is `EXIT`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        match spec.to_ascii_uppercase().as_str() {
+            "EXIT" | "SIGEXIT" | "0" => Some(Condition::Exit),
+            "DEBUG" => Some(Condition::Debug),
+            "ERR" => Some(Condition::Err),
+            "RETURN" => Some(Condition::Return),
+            _ => signal::number(spec).map(Condition::Signal),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Exit => write!(f, "EXIT"),
+            Condition::Signal(n) => match signal::name(*n) {
+                Some(name) => write!(f, "SIG{}", name),
+                None => write!(f, "{}", n),
+            },
+            Condition::Debug => write!(f, "DEBUG"),
+            Condition::Err => write!(f, "ERR"),
+            Condition::Return => write!(f, "RETURN"),
+        }
+    }
+}
+
+/// The commands set with `trap`. An empty command ignores the signal.
+#[derive(Debug, Clone, Default)]
+pub struct TrapTable {
+    actions: BTreeMap<Condition, String>,
+    /// Whether a trap is running; traps do not fire inside one another.
+    pub running: bool,
+}
+
+impl TrapTable {
+    /// The command to run for `cond`, unless it is unset or ignored.
+    pub fn get(&self, cond: Condition) -> Option<&str> {
+        self.actions
+            .get(&cond)
+            .map(String::as_str)
+            .filter(|action| !action.is_empty())
+    }
+
+    /// Sets the command for `cond`, catching or ignoring the signal.
+    pub fn set(&mut self, cond: Condition, action: String) {
+        if let Condition::Signal(sig) = cond {
+            let disposition = if action.is_empty() {
+                Disposition::Ignore
+            } else {
+                Disposition::Catch
+            };
+            signal::set_disposition(sig, disposition).ok();
+        }
+        self.actions.insert(cond, action);
+    }
+
+    /// Removes the trap on `cond`, giving the signal its default action.
+    pub fn reset(&mut self, cond: Condition) {
+        if let Condition::Signal(sig) = cond {
+            signal::set_disposition(sig, Disposition::Default).ok();
+        }
+        self.actions.remove(&cond);
+    }
+
+    /// Removes and returns the command for `cond`, so that it runs once.
+    pub fn take(&mut self, cond: Condition) -> Option<String> {
+        self.actions
+            .remove(&cond)
+            .filter(|action| !action.is_empty())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Condition, &str)> {
+        self.actions
+            .iter()
+            .map(|(cond, action)| (*cond, action.as_str()))
+    }
+
+    /// Clears the traps in a forked subshell, which starts with none set
+    /// but keeps ignoring the signals its parent ignored. Unless ignored,
+    /// `SIGPIPE` gets its default action back from the Rust runtime, so a
+    /// child writing to a closed pipe ends quietly with status 141.
+    pub fn enter_subshell(&mut self) {
+        if self.actions.get(&Condition::Signal(libc::SIGPIPE)) != Some(&String::new()) {
+            signal::set_disposition(libc::SIGPIPE, Disposition::Default).ok();
+        }
+        let caught: Vec<Condition> = self
+            .actions
+            .iter()
+            .filter(|(_, action)| !action.is_empty())
+            .map(|(cond, _)| *cond)
+            .collect();
+        for cond in caught {
+            self.reset(cond);
+        }
+        self.running = false;
+        signal::close_pipe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions() {
+        assert_eq!(Condition::parse("exit"), Some(Condition::Exit));
+        assert_eq!(Condition::parse("0"), Some(Condition::Exit));
+        assert_eq!(
+            Condition::parse("int"),
+            Some(Condition::Signal(libc::SIGINT))
+        );
+        assert_eq!(
+            Condition::parse("SIGTERM"),
+            Some(Condition::Signal(libc::SIGTERM))
+        );
+        assert_eq!(Condition::parse("BOGUS"), None);
+        assert_eq!(Condition::Signal(libc::SIGHUP).to_string(), "SIGHUP");
+        assert!(Condition::Exit < Condition::Signal(1));
+        assert!(Condition::Signal(31) < Condition::Debug);
+    }
+}
@@ -0,0 +1,639 @@
+use crate::cli::arith;
+use crate::cli::error::ShellError;
+use std::collections::BTreeMap;
+
+/// The value of a variable: a string, or an array of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Scalar(String),
+    /// Elements by index. Indices need not be contiguous.
+    Indexed(BTreeMap<usize, String>),
+    /// Elements by key, as declared with `declare -A`.
+    Assoc(BTreeMap<String, String>),
+}
+
+impl Value {
+    /// What `$name` expands to: the string, or element 0 of an array.
+    pub fn first(&self) -> Option<&str> {
+        match self {
+            Value::Scalar(s) => Some(s),
+            Value::Indexed(map) => map.get(&0).map(String::as_str),
+            Value::Assoc(map) => map.get("0").map(String::as_str),
+        }
+    }
+
+    /// The elements in order of index or key, as `${name[@]}` expands to.
+    /// A string is an array of one.
+    pub fn elements(&self) -> Vec<&str> {
+        match self {
+            Value::Scalar(s) => vec![s],
+            Value::Indexed(map) => map.values().map(String::as_str).collect(),
+            Value::Assoc(map) => map.values().map(String::as_str).collect(),
+        }
+    }
+
+    /// The indices or keys, as `${!name[@]}` expands to.
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            Value::Scalar(_) => vec!["0".into()],
+            Value::Indexed(map) => map.keys().map(usize::to_string).collect(),
+            Value::Assoc(map) => map.keys().cloned().collect(),
+        }
+    }
+
+    /// The value as it is written in an assignment: `"x"` for a string,
+    /// `([0]="x" [1]="y")` for an array.
+    pub fn to_assignment(&self) -> String {
+        let items: Vec<String> = match self {
+            Value::Scalar(s) => return format!("\"{}\"", escape(s)),
+            Value::Indexed(map) => map
+                .iter()
+                .map(|(i, v)| format!("[{}]=\"{}\"", i, escape(v)))
+                .collect(),
+            Value::Assoc(map) => map
+                .iter()
+                .map(
+                    |(k, v)| match is_name(k) || k.chars().all(|c| c.is_ascii_digit()) {
+                        true => format!("[{}]=\"{}\"", k, escape(v)),
+                        false => format!("[\"{}\"]=\"{}\"", escape(k), escape(v)),
+                    },
+                )
+                .collect(),
+        };
+        format!("({})", items.join(" "))
+    }
+}
+
+/// A shell variable. A variable can have attributes without a value, as
+/// after `export NAME` for a name that was never set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variable {
+    pub value: Option<Value>,
+    /// Passed in the environment of commands the shell runs.
+    pub exported: bool,
+    /// Cannot be assigned or unset.
+    pub readonly: bool,
+    /// Assignments are evaluated as integers.
+    pub integer: bool,
+    /// Assigned values are converted to lower case.
+    pub lowercase: bool,
+    /// Assigned values are converted to upper case.
+    pub uppercase: bool,
+    /// The value names another variable, which is used in its place.
+    pub nameref: bool,
+}
+
+impl Variable {
+    /// The `declare` command that recreates this variable, as printed by
+    /// `declare -p` and `export -p`.
+    pub fn declaration(&self, name: &str) -> String {
+        let mut flags = String::new();
+        match self.value {
+            Some(Value::Indexed(_)) => flags.push('a'),
+            Some(Value::Assoc(_)) => flags.push('A'),
+            _ => {}
+        }
+        for (on, flag) in [
+            (self.integer, 'i'),
+            (self.nameref, 'n'),
+            (self.readonly, 'r'),
+            (self.exported, 'x'),
+            (self.lowercase, 'l'),
+            (self.uppercase, 'u'),
+        ] {
+            if on {
+                flags.push(flag);
+            }
+        }
+        if flags.is_empty() {
+            flags.push('-');
+        }
+        match &self.value {
+            Some(value) => format!("declare -{} {}={}", flags, name, value.to_assignment()),
+            None => format!("declare -{} {}", flags, name),
+        }
+    }
+}
+
+impl Variable {
+    /// Converts an assigned value to lower or upper case if the variable
+    /// asks for it.
+    fn fold_case(&self, value: String) -> String {
+        if self.lowercase {
+            value.to_lowercase()
+        } else if self.uppercase {
+            value.to_uppercase()
+        } else {
+            value
+        }
+    }
+}
+
+/// Escapes the characters that stay special inside double quotes.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The shell's variables, which are also the environment of the commands
+/// it runs. There is no other copy: the process environment is read once,
+/// at startup.
+#[derive(Debug, Clone, Default)]
+pub struct Variables {
+    vars: BTreeMap<String, Variable>,
+}
+
+impl Variables {
+    /// Imports the process environment, every variable exported.
+    pub fn from_environ() -> Self {
+        let vars = std::env::vars_os()
+            .filter_map(|(name, value)| {
+                let name = name.into_string().ok().filter(|n| is_name(n))?;
+                let var = Variable {
+                    value: Some(Value::Scalar(value.to_string_lossy().into_owned())),
+                    exported: true,
+                    ..Variable::default()
+                };
+                Some((name, var))
+            })
+            .collect();
+        Variables { vars }
+    }
+
+    /// The value of `name`, or of element 0 if it is an array.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(self.resolve(name))?.value.as_ref()?.first()
+    }
+
+    /// The variable and its attributes, whether or not it has a value.
+    pub fn lookup(&self, name: &str) -> Option<&Variable> {
+        self.vars.get(name)
+    }
+
+    /// Assigns `value`, evaluating it first if `name` has the integer
+    /// attribute. Fails if `name` is readonly. An array has its element 0
+    /// assigned instead.
+    pub fn set(&mut self, name: &str, value: impl Into<String>) -> Result<(), ShellError> {
+        self.assign(name, None, value.into(), false)
+    }
+
+    /// Assigns `value` to `name`, or to element `subscript` of it, as
+    /// `name=value` and `name[subscript]=value` do. With `append` the value
+    /// is added to the old one, as `name+=value` does: numerically for an
+    /// integer variable, as text otherwise.
+    pub fn assign(
+        &mut self,
+        name: &str,
+        subscript: Option<&str>,
+        value: String,
+        append: bool,
+    ) -> Result<(), ShellError> {
+        let name = &self.resolve(name).to_string();
+        let var = self.vars.get(name);
+        if var.is_some_and(|v| v.readonly) {
+            return Err(readonly(name));
+        }
+        let integer = var.is_some_and(|v| v.integer);
+        let key = match subscript {
+            Some(sub) => self.key(name, sub)?,
+            None if matches!(self.value(name), Some(Value::Indexed(_))) => Key::Index(0),
+            None => Key::Name("0".into()),
+        };
+        let old = match append {
+            true => self.element(name, &key).unwrap_or_default(),
+            false => "",
+        };
+        let value = if integer {
+            self.integer_value(old)?
+                .wrapping_add(self.integer_value(&value)?)
+                .to_string()
+        } else {
+            format!("{}{}", old, value)
+        };
+
+        let var = self.vars.entry(name.to_string()).or_default();
+        let value = var.fold_case(value);
+        match (&mut var.value, key) {
+            (Some(Value::Indexed(map)), Key::Index(i)) => {
+                map.insert(i, value);
+            }
+            (Some(Value::Assoc(map)), Key::Name(k)) => {
+                map.insert(k, value);
+            }
+            (_, Key::Name(_)) => var.value = Some(Value::Scalar(value)),
+            (old, Key::Index(i)) => {
+                // a string becomes element 0 of the new array
+                let mut map = BTreeMap::new();
+                if let Some(Value::Scalar(s)) = old.take() {
+                    map.insert(0, s);
+                }
+                map.insert(i, value);
+                *old = Some(Value::Indexed(map));
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns a whole array, as `name=(...)` does. Each item has an
+    /// optional subscript; those without one follow the previous element.
+    /// With `append` the items are added to the existing elements.
+    pub fn assign_array(
+        &mut self,
+        name: &str,
+        items: Vec<(Option<String>, String)>,
+        append: bool,
+    ) -> Result<(), ShellError> {
+        let name = &self.resolve(name).to_string();
+        let var = self.vars.get(name);
+        if var.is_some_and(|v| v.readonly) {
+            return Err(readonly(name));
+        }
+        let assoc = self.is_assoc(name);
+        let mut value = match var.and_then(|v| v.value.clone()) {
+            Some(Value::Scalar(s)) if append => Value::Indexed(BTreeMap::from([(0, s)])),
+            Some(value @ (Value::Indexed(_) | Value::Assoc(_))) if append => value,
+            _ if assoc => Value::Assoc(BTreeMap::new()),
+            _ => Value::Indexed(BTreeMap::new()),
+        };
+        self.vars.entry(name.to_string()).or_default().value = Some(value.clone());
+
+        let mut next = match &value {
+            Value::Indexed(map) => map.keys().next_back().map_or(0, |i| i + 1),
+            _ => 0,
+        };
+        for (subscript, item) in items {
+            match (&mut value, subscript) {
+                (Value::Assoc(map), Some(key)) => {
+                    map.insert(key, item);
+                }
+                (Value::Assoc(_), None) => {
+                    let msg = format!(
+                        "{}: {}: must use subscript when assigning associative array",
+                        name, item
+                    );
+                    return Err(ShellError::failed(1, msg));
+                }
+                (Value::Indexed(map), subscript) => {
+                    if let Some(sub) = subscript {
+                        let Key::Index(i) = self.key(name, &sub)? else {
+                            unreachable!("an indexed array has numeric keys");
+                        };
+                        next = i;
+                    }
+                    map.insert(next, item);
+                    next += 1;
+                }
+                (Value::Scalar(_), _) => unreachable!("arrays are assigned as arrays"),
+            }
+        }
+        value = match value {
+            Value::Indexed(map) => Value::Indexed(self.convert_all(name, map)?),
+            Value::Assoc(map) => Value::Assoc(self.convert_all(name, map)?),
+            scalar => scalar,
+        };
+        self.vars.get_mut(name).unwrap().value = Some(value);
+        Ok(())
+    }
+
+    /// Applies the integer and case attributes of `name` to the elements
+    /// of an array assigned to it.
+    fn convert_all<K: Ord>(
+        &self,
+        name: &str,
+        map: BTreeMap<K, String>,
+    ) -> Result<BTreeMap<K, String>, ShellError> {
+        let var = &self.vars[name];
+        map.into_iter()
+            .map(|(k, v)| match var.integer {
+                true => Ok((k, self.integer_value(&v)?.to_string())),
+                false => Ok((k, var.fold_case(v))),
+            })
+            .collect()
+    }
+
+    /// Element `subscript` of `name`, as `${name[subscript]}` expands to.
+    /// A string is element 0 of itself.
+    pub fn get_element(&self, name: &str, subscript: &str) -> Result<Option<&str>, ShellError> {
+        let name = &self.resolve(name).to_string();
+        let key = self.key(name, subscript)?;
+        Ok(self.element(name, &key))
+    }
+
+    fn element(&self, name: &str, key: &Key) -> Option<&str> {
+        match (self.vars.get(name)?.value.as_ref()?, key) {
+            (Value::Indexed(map), Key::Index(i)) => map.get(i).map(String::as_str),
+            (Value::Assoc(map), Key::Name(k)) => map.get(k).map(String::as_str),
+            (Value::Scalar(s), Key::Index(0)) => Some(s),
+            (value, Key::Name(k)) if k == "0" => value.first(),
+            _ => None,
+        }
+    }
+
+    /// Resolves a subscript of `name`: a key if it is an associative
+    /// array, otherwise an index, which counts back from the end if it is
+    /// negative.
+    fn key(&self, name: &str, subscript: &str) -> Result<Key, ShellError> {
+        let bad = || ShellError::failed(1, format!("{}[{}]: bad array subscript", name, subscript));
+        if subscript == "@" || subscript == "*" {
+            return Err(bad());
+        }
+        if self.is_assoc(name) {
+            return Ok(Key::Name(subscript.to_string()));
+        }
+        let index = self.integer_value(subscript)?;
+        let index = if index < 0 {
+            let len = match self.vars.get(name).and_then(|v| v.value.as_ref()) {
+                Some(Value::Indexed(map)) => map.keys().next_back().map_or(0, |i| i + 1),
+                Some(_) => 1,
+                None => 0,
+            };
+            index + len as i64
+        } else {
+            index
+        };
+        usize::try_from(index).map(Key::Index).map_err(|_| bad())
+    }
+
+    /// Whether `name` is an associative array.
+    pub fn is_assoc(&self, name: &str) -> bool {
+        self.value(name)
+            .is_some_and(|v| matches!(v, Value::Assoc(_)))
+    }
+
+    /// The value of `name`, which may be an array.
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.vars.get(self.resolve(name))?.value.as_ref()
+    }
+
+    /// Follows namerefs from `name` to the variable they refer to. A loop
+    /// of references ends wherever the limit is reached.
+    fn resolve<'a>(&'a self, mut name: &'a str) -> &'a str {
+        for _ in 0..NAMEREF_LIMIT {
+            match self.vars.get(name) {
+                Some(Variable {
+                    nameref: true,
+                    value: Some(Value::Scalar(target)),
+                    ..
+                }) if !target.is_empty() => name = target,
+                _ => break,
+            }
+        }
+        name
+    }
+
+    /// Makes `name` refer to another variable, as `declare -n` does,
+    /// pointing it at `target` if given.
+    pub fn set_nameref(&mut self, name: &str, target: Option<&str>) -> Result<(), ShellError> {
+        if self.vars.get(name).is_some_and(|v| v.readonly) {
+            return Err(readonly(name));
+        }
+        if let Some(target) = target {
+            if !is_name(target) {
+                let msg = format!("`{}': invalid variable name for name reference", target);
+                return Err(ShellError::failed(1, msg));
+            }
+            if target == name {
+                let msg = format!("{}: nameref variable self references not allowed", name);
+                return Err(ShellError::failed(1, msg));
+            }
+        }
+        let var = self.vars.entry(name.to_string()).or_default();
+        var.nameref = true;
+        if let Some(target) = target {
+            var.value = Some(Value::Scalar(target.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Turns `name` back into an ordinary variable holding the name it
+    /// referred to.
+    pub fn clear_nameref(&mut self, name: &str) {
+        if let Some(var) = self.vars.get_mut(name) {
+            var.nameref = false;
+        }
+    }
+
+    /// Makes `name` an array, as `declare -a` or `declare -A` does. A
+    /// string becomes its element 0, but an array cannot change kind.
+    pub fn declare_array(&mut self, name: &str, assoc: bool) -> Result<(), ShellError> {
+        let name = &self.resolve(name).to_string();
+        let var = self.vars.entry(name.to_string()).or_default();
+        let conversion = match &var.value {
+            Some(Value::Indexed(_)) if assoc => Some("indexed to associative"),
+            Some(Value::Assoc(_)) if !assoc => Some("associative to indexed"),
+            _ => None,
+        };
+        if let Some(conversion) = conversion {
+            let msg = format!("{}: cannot convert {} array", name, conversion);
+            return Err(ShellError::failed(1, msg));
+        }
+        var.value = match var.value.take() {
+            Some(value @ (Value::Indexed(_) | Value::Assoc(_))) => Some(value),
+            Some(Value::Scalar(s)) if assoc => {
+                Some(Value::Assoc(BTreeMap::from([("0".into(), s)])))
+            }
+            Some(Value::Scalar(s)) => Some(Value::Indexed(BTreeMap::from([(0, s)]))),
+            None if assoc => Some(Value::Assoc(BTreeMap::new())),
+            None => Some(Value::Indexed(BTreeMap::new())),
+        };
+        Ok(())
+    }
+
+    /// Removes `name` along with its attributes. Fails if it is readonly.
+    pub fn unset(&mut self, name: &str) -> Result<(), ShellError> {
+        let name = &self.resolve(name).to_string();
+        if self.vars.get(name).is_some_and(|v| v.readonly) {
+            return Err(readonly(name));
+        }
+        self.vars.remove(name);
+        Ok(())
+    }
+
+    /// Removes element `subscript` of `name`. Removing element 0 of a
+    /// string unsets it.
+    pub fn unset_element(&mut self, name: &str, subscript: &str) -> Result<(), ShellError> {
+        let name = &self.resolve(name).to_string();
+        if self.vars.get(name).is_some_and(|v| v.readonly) {
+            return Err(readonly(name));
+        }
+        let key = self.key(name, subscript)?;
+        let Some(var) = self.vars.get_mut(name) else {
+            return Ok(());
+        };
+        match (&mut var.value, key) {
+            (Some(Value::Indexed(map)), Key::Index(i)) => {
+                map.remove(&i);
+            }
+            (Some(Value::Assoc(map)), Key::Name(k)) => {
+                map.remove(&k);
+            }
+            (Some(Value::Scalar(_)), Key::Index(0)) => {
+                var.value = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Puts back a variable saved before an assignment that only lasts
+    /// as long as one command, as `name=value cmd` makes.
+    pub fn restore(&mut self, name: &str, saved: Option<Variable>) {
+        match saved {
+            Some(var) => self.vars.insert(name.to_string(), var),
+            None => self.vars.remove(name),
+        };
+    }
+
+    /// Turns the export attribute on or off, creating `name` without a
+    /// value if it does not exist.
+    pub fn export(&mut self, name: &str, on: bool) {
+        self.vars.entry(name.to_string()).or_default().exported = on;
+    }
+
+    /// Makes `name` readonly, which cannot be undone.
+    pub fn set_readonly(&mut self, name: &str) {
+        self.vars.entry(name.to_string()).or_default().readonly = true;
+    }
+
+    /// Turns the integer attribute on or off. Only later assignments are
+    /// evaluated.
+    pub fn set_integer(&mut self, name: &str, on: bool) {
+        self.vars.entry(name.to_string()).or_default().integer = on;
+    }
+
+    /// Turns conversion of assigned values to lower case on or off. It
+    /// replaces conversion to upper case.
+    pub fn set_lowercase(&mut self, name: &str, on: bool) {
+        let var = self.vars.entry(name.to_string()).or_default();
+        var.lowercase = on;
+        var.uppercase &= !on;
+    }
+
+    /// Turns conversion of assigned values to upper case on or off. It
+    /// replaces conversion to lower case.
+    pub fn set_uppercase(&mut self, name: &str, on: bool) {
+        let var = self.vars.entry(name.to_string()).or_default();
+        var.uppercase = on;
+        var.lowercase &= !on;
+    }
+
+    /// Every variable in name order, including those without a value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Variable)> {
+        self.vars.iter().map(|(name, var)| (name.as_str(), var))
+    }
+
+    /// The exported variables that have values, for a child's environment.
+    pub fn environ(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars.iter().filter_map(|(name, var)| match &var.value {
+            Some(Value::Scalar(value)) if var.exported => Some((name.as_str(), value.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Evaluates an assignment to an integer variable, or an array
+    /// subscript, as an arithmetic expression. Empty and unset values count
+    /// as 0.
+    pub fn integer_value(&self, expr: &str) -> Result<i64, ShellError> {
+        let lookup = |name: &str| self.get(name).map(str::to_string);
+        arith::evaluate(expr, &lookup).map_err(|e| ShellError::failed(1, e))
+    }
+}
+
+/// For building a set of plain variables, as tests do.
+impl FromIterator<(String, String)> for Variables {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let vars = iter
+            .into_iter()
+            .map(|(name, value)| {
+                let var = Variable {
+                    value: Some(Value::Scalar(value)),
+                    ..Variable::default()
+                };
+                (name, var)
+            })
+            .collect();
+        Variables { vars }
+    }
+}
+
+/// A resolved subscript.
+enum Key {
+    Index(usize),
+    Name(String),
+}
+
+/// How many namerefs are followed before giving up.
+const NAMEREF_LIMIT: usize = 8;
+
+fn readonly(name: &str) -> ShellError {
+    ShellError::failed(1, format!("{}: readonly variable", name))
+}
+
+/// Whether `s` can name a variable.
+pub fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c == '_' || c.is_ascii_alphabetic())
+        && s.chars().all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes() {
+        let mut vars = Variables::default();
+        vars.set("A", "1").unwrap();
+        vars.export("B", true);
+        assert_eq!(vars.get("B"), None);
+        assert_eq!(vars.environ().count(), 0);
+        vars.set("B", "x y").unwrap();
+        assert_eq!(vars.environ().collect::<Vec<_>>(), [("B", "x y")]);
+
+        vars.set_integer("N", true);
+        vars.set("N", " 42 ").unwrap();
+        assert_eq!(vars.get("N"), Some("42"));
+        vars.set("N", "A").unwrap();
+        assert_eq!(vars.get("N"), Some("1"));
+        assert!(vars.set("N", "1 +").is_err());
+
+        vars.set_readonly("A");
+        assert_eq!(
+            vars.set("A", "2").unwrap_err().to_string(),
+            "A: readonly variable"
+        );
+        assert!(vars.unset("A").is_err());
+        assert_eq!(
+            vars.lookup("A").unwrap().declaration("A"),
+            "declare -r A=\"1\""
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        let mut vars = Variables::default();
+        let items = vec![(None, "x".into()), (Some("4".into()), "y".into())];
+        vars.assign_array("a", items, false).unwrap();
+        vars.assign("a", Some("-1"), "z".into(), true).unwrap();
+        vars.set("a", "w").unwrap();
+        assert_eq!(vars.value("a").unwrap().elements(), ["w", "yz"]);
+        assert_eq!(vars.value("a").unwrap().keys(), ["0", "4"]);
+        assert!(vars.get_element("a", "-6").is_err());
+        vars.unset_element("a", "0").unwrap();
+        assert_eq!(vars.get("a"), None);
+        assert_eq!(vars.environ().count(), 0);
+
+        vars.set("s", "x").unwrap();
+        assert!(vars.declare_array("s", true).is_ok());
+        assert_eq!(vars.get_element("s", "0").unwrap(), Some("x"));
+        assert!(vars.declare_array("s", false).is_err());
+        assert!(vars
+            .assign_array("s", vec![(None, "y".into())], true)
+            .is_err());
+    }
+}
@@ -0,0 +1,11 @@
+//! A small POSIX-style shell that can also be embedded: build a [`Shell`],
+//! register extra [`Command`]s on it and run strings or scripts, getting
+//! back an [`Outcome`] with the exit status and captured output.
+
+pub mod cli;
+mod shell;
+pub mod utils;
+
+pub use cli::command::{Command, CommandContext, IoHandle};
+pub use cli::error::{Control, ExitStatus, ShellError};
+pub use shell::{Outcome, Shell, ShellBuilder};
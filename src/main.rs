@@ -1,48 +1,71 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use codecrafters_shell::cli::command::IoHandle;
+use codecrafters_shell::cli::parser::parse_list;
+use codecrafters_shell::Shell;
 use std::io::{self, Write};
-use std::rc::Rc;
-mod cli;
-mod utils;
-use cli::command::{CommandContext, IoHandle};
-use cli::commands;
-use cli::commands::type_cmd::TypeCommand;
-use cli::registry::CommandRegistry;
 
 fn main() {
-    let mut ctx = CommandContext {
-        stdin: IoHandle::Stdin,
-        stdout: IoHandle::Stdout,
-        stderr: IoHandle::Stderr,
-        env: HashMap::new(),
-    };
+    let mut args = std::env::args();
+    let name = args.next().unwrap_or_else(|| "shell".into());
+    let script = args.next();
+    let interactive = script.is_none() && unsafe { libc::isatty(libc::STDIN_FILENO) } == 1;
+    let mut shell = Shell::builder()
+        .arg0(script.as_deref().unwrap_or(&name))
+        .args(args)
+        .interactive(interactive)
+        .inherit_stdin(true)
+        .build();
 
-    if let Ok(dir) = std::env::current_dir() {
-        ctx.env.insert("PWD".into(), dir.display().to_string());
+    if let Some(path) = script {
+        if let Err(e) = shell.execute_script(&path) {
+            shell.context().last_status = e.status();
+        }
+        std::process::exit(shell.shutdown());
     }
-
-    let mut registry = CommandRegistry::new();
-    commands::register_all(&mut registry);
-    // Register 'type' command seperately as it holds
-    // back reference to CommandRegistry
-    let reg_rc = Rc::new(RefCell::new(registry));
-    let type_cmd = Rc::new(TypeCommand {
-        registry: Rc::downgrade(&reg_rc),
-    });
-    reg_rc.borrow_mut().register(type_cmd);
-
-    let _reg_rc = reg_rc.borrow();
     loop {
-        print!("$ ");
-        io::stdout().flush().unwrap();
+        let ctx = shell.context();
+        // Finished jobs are reaped either way, but only reported to a user.
+        let finished = ctx.jobs.reap();
+        for (job, status) in finished.into_iter().filter(|_| interactive) {
+            let state = match status {
+                0 => "Done".to_string(),
+                n => format!("Exit {}", n),
+            };
+            let notice = format!("[{}]  {:<24}{}", job.id, state, job.command);
+            ctx.stderr.write_line(&notice).ok();
+        }
+
+        let ps1 = shell.prompt("PS1");
+        write!(io::stdout(), "{}", ps1)
+            .and_then(|_| io::stdout().flush())
+            .ok();
         let mut line = String::new();
 
-        if io::stdin().read_line(&mut line).is_err() {
-            break;
+        match IoHandle::Stdin.read_line(&mut line) {
+            Ok(0) if shell.ignore_eof() => continue,
+            Ok(0) | Err(_) => {
+                if interactive {
+                    eprintln!("exit");
+                }
+                break;
+            }
+            Ok(_) => {}
+        }
+
+        while parse_list::is_incomplete(&line) {
+            let ps2 = shell.prompt("PS2");
+            write!(io::stdout(), "{}", ps2)
+                .and_then(|_| io::stdout().flush())
+                .ok();
+            match IoHandle::Stdin.read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
         }
 
-        if !_reg_rc.execute(&line, &mut ctx) {
+        if let Err(e) = shell.execute(&line) {
+            shell.context().last_status = e.status();
             break;
         }
     }
+    std::process::exit(shell.shutdown());
 }
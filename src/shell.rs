@@ -0,0 +1,857 @@
+use crate::cli::command::{Command, CommandContext, IoHandle};
+use crate::cli::commands;
+use crate::cli::commands::builtin::BuiltinCommand;
+use crate::cli::commands::command::CommandCommand;
+use crate::cli::commands::help::HelpCommand;
+use crate::cli::commands::type_cmd::TypeCommand;
+use crate::cli::diagnostic::Origin;
+use crate::cli::dirs::DirStack;
+use crate::cli::error::{ExitStatus, ShellError};
+use crate::cli::hash::HashTable;
+use crate::cli::jobs::JobTable;
+use crate::cli::options::ShellOptions;
+use crate::cli::params::Params;
+use crate::cli::parser::parse_list;
+use crate::cli::prompt;
+use crate::cli::registry::CommandRegistry;
+use crate::cli::traps::TrapTable;
+use crate::cli::vars::Variables;
+use crate::utils::{paths, sys};
+use std::cell::{Ref, RefCell};
+use std::mem;
+use std::rc::Rc;
+
+/// A shell instance: the registered commands plus the state they run in.
+///
+/// ```
+/// use codecrafters_shell::Shell;
+///
+/// let mut shell = Shell::builder().var("NAME", "world").build();
+/// let outcome = shell.run("echo hello $NAME");
+/// assert_eq!(outcome.stdout, "hello world\n");
+/// ```
+pub struct Shell {
+    registry: Rc<RefCell<CommandRegistry>>,
+    ctx: CommandContext,
+    /// Ends of input ignored in a row; see `ignore_eof`.
+    eofs: usize,
+}
+
+/// Configures a `Shell` before it starts.
+#[derive(Default)]
+pub struct ShellBuilder {
+    commands: Vec<Rc<dyn Command>>,
+    vars: Vec<(String, String)>,
+    arg0: String,
+    args: Vec<String>,
+    interactive: bool,
+    inherit_stdin: bool,
+}
+
+/// What running some input produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether the shell stopped early, through `exit` or an error that
+    /// ends a non-interactive shell. Later input still runs.
+    pub exited: bool,
+}
+
+impl Outcome {
+    pub fn success(&self) -> bool {
+        self.status == 0
+    }
+}
+
+impl ShellBuilder {
+    /// Adds a command, replacing any builtin of the same name.
+    pub fn command(mut self, cmd: impl Command + 'static) -> Self {
+        self.commands.push(Rc::new(cmd));
+        self
+    }
+
+    /// Sets a shell variable.
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.vars.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets `$0`, the name the shell reports itself by.
+    pub fn arg0(mut self, name: &str) -> Self {
+        self.arg0 = name.to_string();
+        self
+    }
+
+    /// Sets the positional parameters, `$1` onwards.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether the shell is interactive, which keeps it going after syntax
+    /// and expansion errors. Off by default.
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Lets commands read the process's standard input. By default they see
+    /// end of input.
+    pub fn inherit_stdin(mut self, inherit: bool) -> Self {
+        self.inherit_stdin = inherit;
+        self
+    }
+
+    pub fn build(self) -> Shell {
+        let ctx = CommandContext {
+            stdin: if self.inherit_stdin {
+                IoHandle::Stdin
+            } else {
+                IoHandle::Null
+            },
+            stdout: IoHandle::Stdout,
+            stderr: IoHandle::Stderr,
+            vars: startup_vars(self.vars),
+            params: Params {
+                arg0: self.arg0,
+                positional: self.args,
+                ..Params::default()
+            },
+            last_status: 0,
+            jobs: JobTable::default(),
+            options: ShellOptions::default(),
+            interactive: self.interactive,
+            errexit_ignored: 0,
+            hash: HashTable::default(),
+            dir_stack: DirStack::default(),
+            traps: TrapTable::default(),
+        };
+
+        let mut registry = CommandRegistry::new();
+        commands::register_all(&mut registry);
+        // Register 'type', 'command', 'builtin' and 'help' seperately as they
+        // hold back reference to CommandRegistry
+        let reg_rc = Rc::new(RefCell::new(registry));
+        let weak = Rc::downgrade(&reg_rc);
+        {
+            let mut registry = reg_rc.borrow_mut();
+            registry.register(Rc::new(TypeCommand {
+                registry: weak.clone(),
+            }));
+            registry.register(Rc::new(CommandCommand {
+                registry: weak.clone(),
+            }));
+            registry.register(Rc::new(BuiltinCommand {
+                registry: weak.clone(),
+            }));
+            registry.register(Rc::new(HelpCommand { registry: weak }));
+            for cmd in self.commands {
+                registry.register(cmd);
+            }
+        }
+
+        Shell {
+            registry: reg_rc,
+            ctx,
+            eofs: 0,
+        }
+    }
+}
+
+/// The variables a shell starts with: the process environment, adjusted
+/// for a new shell, then `extra`.
+fn startup_vars(extra: Vec<(String, String)>) -> Variables {
+    let mut vars = Variables::from_environ();
+    // An inherited PWD keeps the symlinks the parent used, but only if it
+    // still names the directory we started in.
+    let pwd = vars
+        .get("PWD")
+        .filter(|pwd| paths::is_valid_pwd(pwd))
+        .map(str::to_string)
+        .or_else(|| Some(std::env::current_dir().ok()?.display().to_string()));
+    if let Some(pwd) = pwd {
+        vars.set("PWD", pwd).ok();
+        vars.export("PWD", true);
+    }
+    // OLDPWD belongs to the parent; ours is exported once `cd` sets it.
+    vars.unset("OLDPWD").ok();
+    vars.export("OLDPWD", true);
+
+    let level = vars.get("SHLVL").and_then(|l| l.parse::<i64>().ok());
+    let level = level.filter(|&l| l >= 0).unwrap_or(0) + 1;
+    vars.set("SHLVL", level.to_string()).ok();
+    vars.export("SHLVL", true);
+    if vars.get("SHELL").is_none() {
+        if let Ok(exe) = std::env::current_exe() {
+            vars.set("SHELL", exe.display().to_string()).ok();
+        }
+    }
+
+    for (name, value) in extra {
+        vars.set(&name, value).ok();
+    }
+    let ppid = unsafe { libc::getppid() };
+    vars.set("PPID", ppid.to_string()).ok();
+    vars.set_readonly("PPID");
+    vars
+}
+
+impl Shell {
+    pub fn builder() -> ShellBuilder {
+        ShellBuilder::default()
+    }
+
+    /// Runs `source` and returns its status along with everything it wrote,
+    /// including the output of external commands it started.
+    pub fn run(&mut self, source: &str) -> Outcome {
+        self.capture(|shell| shell.execute(source))
+    }
+
+    /// Runs the script at `path`, capturing its output as `run` does.
+    pub fn run_script(&mut self, path: &str) -> Outcome {
+        self.capture(|shell| shell.execute_script(path))
+    }
+
+    /// Runs `source` with output going wherever the shell's output goes.
+    /// `exit`, and errors that end a non-interactive shell such as syntax
+    /// errors, come back as `Control::Exit` for the caller to act on.
+    pub fn execute(&mut self, source: &str) -> Result<ExitStatus, ShellError> {
+        self.eofs = 0;
+        self.registry.borrow().execute(source, &mut self.ctx)
+    }
+
+    /// Runs the script at `path` a complete command at a time, so syntax
+    /// errors report the line they occur on. A script that cannot be read
+    /// sets status 127.
+    pub fn execute_script(&mut self, path: &str) -> Result<ExitStatus, ShellError> {
+        let registry = self.registry.borrow();
+        let ctx = &mut self.ctx;
+        let source = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                let msg = format!("shell: {}: {}", path, sys::error_message(&e));
+                ctx.stderr.write_line(&msg).ok();
+                ctx.last_status = 127;
+                return Ok(127);
+            }
+        };
+        let mut chunk = String::new();
+        let mut start = 1;
+        for (i, line) in source.split_inclusive('\n').enumerate() {
+            if chunk.is_empty() {
+                start = i + 1;
+            }
+            chunk.push_str(line);
+            if parse_list::is_incomplete(&chunk) {
+                continue;
+            }
+            let origin = Origin {
+                name: path,
+                line: start,
+            };
+            registry.execute_at(&chunk, Some(origin), ctx)?;
+            chunk.clear();
+        }
+        if !chunk.is_empty() {
+            let origin = Origin {
+                name: path,
+                line: start,
+            };
+            registry.execute_at(&chunk, Some(origin), ctx)?;
+        }
+        Ok(ctx.last_status)
+    }
+
+    /// Whether to keep reading after reaching end of input. An interactive
+    /// shell ignores `IGNOREEOF` ends of input in a row (10 if it is set
+    /// to something other than a number), reminding the user to `exit`.
+    pub fn ignore_eof(&mut self) -> bool {
+        if !self.ctx.interactive {
+            return false;
+        }
+        let Some(limit) = self.ctx.get_var("IGNOREEOF") else {
+            return false;
+        };
+        let limit = limit.parse().unwrap_or(10);
+        if self.eofs >= limit {
+            return false;
+        }
+        self.eofs += 1;
+        self.ctx
+            .stderr
+            .write_line("Use \"exit\" to leave the shell.")
+            .ok();
+        true
+    }
+
+    /// Ends the shell, whether through `exit`, end of input or the end of
+    /// a script, and returns the status it should exit with. The `EXIT`
+    /// trap runs first. With `huponexit` set, background jobs still
+    /// running are sent `SIGHUP`.
+    pub fn shutdown(&mut self) -> ExitStatus {
+        self.registry.borrow().run_exit_trap(&mut self.ctx);
+        if self.ctx.options.huponexit {
+            self.ctx.jobs.reap();
+            self.ctx.jobs.hangup();
+        }
+        self.ctx.last_status
+    }
+
+    /// Renders the prompt in the variable `name` (`PS1` or `PS2`), falling
+    /// back to the default for that prompt.
+    pub fn prompt(&mut self, name: &str) -> String {
+        let template = self.ctx.get_var(name).unwrap_or_else(|| {
+            match name {
+                "PS2" => prompt::DEFAULT_PS2,
+                _ => prompt::DEFAULT_PS1,
+            }
+            .into()
+        });
+        prompt::render(&template, &mut self.ctx, &self.registry.borrow())
+    }
+
+    /// The status of the last command run.
+    pub fn status(&self) -> i32 {
+        self.ctx.last_status
+    }
+
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.ctx.get_var(name)
+    }
+
+    /// Sets a shell variable, failing if it is readonly.
+    pub fn set_var(&mut self, name: &str, value: &str) -> Result<(), ShellError> {
+        self.ctx.vars.set(name, value)
+    }
+
+    /// Adds a command after the shell has been built.
+    pub fn register(&mut self, cmd: impl Command + 'static) {
+        self.registry.borrow_mut().register(Rc::new(cmd));
+    }
+
+    pub fn context(&mut self) -> &mut CommandContext {
+        &mut self.ctx
+    }
+
+    pub fn registry(&self) -> Ref<'_, CommandRegistry> {
+        self.registry.borrow()
+    }
+
+    /// Runs `f` with standard output and error going to in-memory buffers.
+    fn capture(&mut self, f: impl FnOnce(&mut Self) -> Result<ExitStatus, ShellError>) -> Outcome {
+        let out = Rc::new(RefCell::new(vec![]));
+        let err = Rc::new(RefCell::new(vec![]));
+        let stdout = mem::replace(&mut self.ctx.stdout, IoHandle::Buffer(out.clone()));
+        let stderr = mem::replace(&mut self.ctx.stderr, IoHandle::Buffer(err.clone()));
+        let result = f(self);
+        if let Err(e) = &result {
+            self.ctx.last_status = e.status();
+        }
+        self.ctx.stdout = stdout;
+        self.ctx.stderr = stderr;
+        let text = |buf: Rc<RefCell<Vec<u8>>>| String::from_utf8_lossy(&buf.borrow()).into_owned();
+        Outcome {
+            status: self.ctx.last_status,
+            stdout: text(out),
+            stderr: text(err),
+            exited: result.is_err(),
+        }
+    }
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Shell::builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Greet;
+
+    impl Command for Greet {
+        fn name(&self) -> &'static str {
+            "greet"
+        }
+
+        fn description(&self) -> &'static str {
+            "Greet someone"
+        }
+
+        fn execute(
+            &self,
+            args: &[&str],
+            ctx: &mut CommandContext,
+        ) -> Result<ExitStatus, ShellError> {
+            let name = args
+                .first()
+                .ok_or_else(|| ShellError::failed(2, "missing name"))?;
+            ctx.write_stdout(format!("hi {}\n", name).as_bytes())?;
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn captures_builtins_and_children() {
+        let mut shell = Shell::builder().command(Greet).var("WHO", "you").build();
+        let outcome = shell.run("greet $WHO; greet");
+        assert_eq!(outcome.stdout, "hi you\n");
+        assert_eq!(outcome.stderr, "greet: missing name\n");
+        assert_eq!(outcome.status, 2);
+
+        let outcome = shell.run("printf 'a\\nb\\n' | /bin/cat; (/bin/echo c)");
+        assert_eq!(outcome.stdout, "a\nb\nc\n");
+        assert!(outcome.success());
+
+        let outcome = shell.run("echo $(greet x); ls /nonexistent");
+        assert_eq!(outcome.stdout, "hi x\n");
+        assert!(outcome.stderr.contains("nonexistent"));
+        assert_ne!(outcome.status, 0);
+    }
+
+    #[test]
+    fn redirection_failures() {
+        let mut shell = Shell::default();
+        let outcome = shell.run("cat < /missing");
+        assert_eq!(
+            outcome.stderr,
+            "shell: /missing: No such file or directory\n"
+        );
+        assert_eq!(outcome.status, 1);
+
+        let outcome = shell.run("echo x > /nonexistent/dir/f; echo $?");
+        assert_eq!(outcome.stdout, "1\n");
+        assert_eq!(
+            outcome.stderr,
+            "shell: /nonexistent/dir/f: No such file or directory\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("shell-clobber-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("f");
+        let script = format!(
+            "set -C; echo a > {f}; echo b > {f}; echo c >| {f}; echo d > /dev/null",
+            f = file.display()
+        );
+        let outcome = shell.run(&script);
+        assert_eq!(
+            outcome.stderr,
+            format!(
+                "shell: {}: cannot overwrite existing file\n",
+                file.display()
+            )
+        );
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "c\n");
+        assert!(outcome.success());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_running_a_command_hashes_it() {
+        let mut shell = Shell::builder().var("PATH", "/bin").build();
+        let outcome = shell.run("type sh; type -P sh; command -v sh; hash");
+        assert_eq!(
+            outcome.stdout,
+            "sh is /bin/sh\n/bin/sh\n/bin/sh\nhash: hash table empty\n"
+        );
+
+        let outcome = shell.run("sh -c true; hash");
+        assert_eq!(outcome.stdout, "hits\tcommand\n   1\t/bin/sh\n");
+    }
+
+    #[test]
+    fn type_command_and_builtin() {
+        let mut shell = Shell::builder().var("PATH", "/bin").build();
+        let outcome = shell.run("type -t cd sh nosuch");
+        assert_eq!(outcome.stdout, "builtin\nfile\n");
+        assert_eq!(outcome.status, 1);
+
+        let outcome = shell.run("type -p cd sh; type -P cd");
+        assert_eq!(outcome.stdout, "/bin/sh\n");
+        assert_eq!(outcome.status, 1);
+
+        let outcome = shell.run("type -a echo");
+        assert_eq!(
+            outcome.stdout,
+            "echo is a shell builtin\necho is /bin/echo\n"
+        );
+
+        let outcome = shell.run("command -V cd; command -v nosuch");
+        assert_eq!(outcome.stdout, "cd is a shell builtin\n");
+        assert_eq!(outcome.status, 1);
+
+        let outcome = shell.run("builtin echo hi; builtin sh");
+        assert_eq!(outcome.stdout, "hi\n");
+        assert_eq!(outcome.stderr, "builtin: sh: not a shell builtin\n");
+        assert_eq!(outcome.status, 1);
+    }
+
+    #[test]
+    fn help_and_help_flags() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run("help -s cd; help -d pwd; pwd --help");
+        assert!(outcome.stdout.starts_with(
+            "cd: cd [-L|-P] [dir | -]\npwd - Print the current working directory\npwd: pwd [-LP]\n"
+        ));
+
+        let outcome = shell.run("help");
+        assert!(outcome.stdout.lines().any(|line| line
+            == " read [-rs] [-a array] [-d delim] [->  Read a line from standard input into variables"));
+
+        let outcome = shell.run("help nosuch");
+        assert_eq!(outcome.stderr, "help: no help topics match `nosuch'.\n");
+        assert_eq!(outcome.status, 1);
+    }
+
+    #[test]
+    fn help_flag_is_an_operand_to_echo_and_test() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run("x=--help; echo \"$x\"; echo --help");
+        assert_eq!(outcome.stdout, "--help\n--help\n");
+        assert_eq!(shell.run("test --help").status, 0);
+        assert_eq!(shell.run("[ --help ]").status, 0);
+    }
+
+    #[test]
+    fn read_exact_chars_keeps_whitespace() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run(
+            "printf 'a ' | { read -N2 x; echo \"[$x]\"; }; \
+             printf ' a b' | { read -N4 x y; echo \"[$x][$y]\"; }",
+        );
+        assert_eq!(outcome.stdout, "[a ]\n[ a b][]\n");
+    }
+
+    #[test]
+    fn builtins_report_write_errors() {
+        let mut shell = Shell::builder().var("PATH", "/bin").build();
+        let outcome = shell.run("echo hi > /dev/full");
+        assert_eq!(outcome.status, 1);
+        assert_eq!(
+            outcome.stderr,
+            "echo: write error: No space left on device\n"
+        );
+
+        for cmd in [
+            "pwd",
+            "declare -p",
+            "export -p",
+            "trap -p",
+            "kill -l",
+            "set -o",
+            "hash",
+            "dirs",
+            "type cd",
+            "help",
+        ] {
+            let outcome = shell.run(&format!("trap : EXIT; {} > /dev/full", cmd));
+            let name = cmd.split(' ').next().unwrap();
+            assert_eq!(outcome.status, 1, "{}", cmd);
+            assert_eq!(
+                outcome.stderr,
+                format!("{}: write error: No space left on device\n", name),
+            );
+        }
+    }
+
+    #[test]
+    fn conditional_integer_operands_are_arithmetic() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run("n=3; [[ 1+1 -eq 2 && n*2 -gt 5 && 7 -le 010 ]] && echo yes");
+        assert_eq!(outcome.stdout, "yes\n");
+        assert_eq!(shell.run("[[ 2 -lt 1+1 ]]").status, 1);
+    }
+
+    #[test]
+    fn writes_to_closed_pipes_end_quietly() {
+        let mut shell = Shell::builder().var("PATH", "/bin:/usr/bin").build();
+        let outcome = shell.run(
+            "set -o pipefail; printf '%s\\n' $(seq 1 100000) | head -1; echo $?; \
+             (sleep 0.1; echo a) | true; echo $?",
+        );
+        assert_eq!(outcome.stdout, "1\n141\n141\n");
+        assert_eq!(outcome.stderr, "");
+    }
+
+    #[test]
+    fn buffered_output_keeps_its_order() {
+        let mut shell = Shell::builder().build();
+        let path = std::env::temp_dir().join(format!("shell-order-{}", std::process::id()));
+        let outcome = shell.run(&format!(
+            "{{ printf a; /bin/echo b; echo c; }} > {0}; (printf d; /bin/echo e) >> {0}; \
+             /bin/cat {0}",
+            path.display()
+        ));
+        std::fs::remove_file(&path).ok();
+        assert_eq!(outcome.stdout, "ab\nc\nde\n");
+    }
+
+    #[test]
+    fn exit_stops_the_input() {
+        let mut shell = Shell::default();
+        let outcome = shell.run("echo a; (exit 4); echo $?; exit 3; echo b");
+        assert_eq!(outcome.stdout, "a\n4\n");
+        assert_eq!(outcome.status, 3);
+        assert!(outcome.exited);
+
+        let outcome = shell.run("exit abc");
+        assert_eq!(outcome.stderr, "exit: abc: numeric argument required\n");
+        assert_eq!((outcome.status, outcome.exited), (2, true));
+
+        let outcome = shell.run("exit 1 2; echo b");
+        assert_eq!(outcome.stderr, "exit: too many arguments\n");
+        assert_eq!(outcome.stdout, "");
+        assert_eq!((outcome.status, outcome.exited), (1, true));
+    }
+
+    #[test]
+    fn assignments_before_commands() {
+        let mut shell = Shell::default();
+        let outcome = shell.run("A=1 B=$A; X=$B /usr/bin/env | grep ^X=; echo $A$B$X");
+        assert_eq!(outcome.stdout, "X=1\n11\n");
+
+        let outcome = shell.run("set -x; C=\"a b\" true");
+        assert_eq!(outcome.stderr, "+ C='a b' true\n");
+        assert_eq!(shell.var("C"), None);
+
+        shell.run("set +x");
+        for quoted in ["'FOO'=1", "\"FOO\"=1", "F\\OO=1"] {
+            let outcome = shell.run(&format!("{} true", quoted));
+            assert_eq!(outcome.stderr, "FOO=1: not found\n");
+            assert_eq!(outcome.status, 127);
+        }
+        assert_eq!(shell.var("FOO"), None);
+    }
+
+    #[test]
+    fn positional_parameters() {
+        let mut shell = Shell::builder().arg0("sh").args(["a b", "", "c"]).build();
+        let outcome = shell.run("printf '[%s]' \"$@\" $@ $# $0; shift 2; echo \" $*\"");
+        assert_eq!(outcome.stdout, "[a b][][c][a][b][c][3][sh] c\n");
+
+        let outcome = shell.run("set -- x \"y z\"; printf '[%s]' \"$*\" \"$2\"; shift 3");
+        assert_eq!(outcome.stdout, "[x y z][y z]");
+        assert_eq!(outcome.status, 1);
+    }
+
+    #[test]
+    fn parameter_operators() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run(
+            "f=archive.tar.gz; printf '[%s]' ${f%.*} ${f##*.} ${f//a/A} ${f:1:-3} ${#f} ${f^}",
+        );
+        assert_eq!(
+            outcome.stdout,
+            "[archive.tar][gz][Archive.tAr.gz][rchive.tar][14][Archive.tar.gz]"
+        );
+
+        let outcome = shell.run("ref=f; printf '[%s]' ${u:-a b} \"${u-a b}\" ${u:=x} $u ${!ref}");
+        assert_eq!(outcome.stdout, "[a][b][a b][x][x][archive.tar.gz]");
+
+        let outcome = shell.run(
+            "s=abc; a=(x y); big=9223372036854775807; \
+             printf '[%s]' \"${s:9223372036854775807:9223372036854775807}\" ${s:1:big} \
+             \"${a[@]:big:big}\" ${a[@]:1:big}",
+        );
+        assert_eq!(outcome.stdout, "[][bc][y]");
+        assert_eq!(outcome.status, 0);
+
+        let outcome = shell.run(
+            "printf '[%s]' \"${s:999999999999999999999}\" ${s:1:999999999999999999999}; \
+             /bin/true & printf '[%s]' ${!:+set}",
+        );
+        assert_eq!(outcome.stdout, "[][bc][set]");
+
+        let outcome = shell.run("echo ${nope:?}");
+        assert_eq!(outcome.stderr, "shell: nope: parameter null or not set\n");
+        let outcome = shell.run("echo ${nope?}");
+        assert_eq!(outcome.stderr, "shell: nope: parameter not set\n");
+
+        let outcome = shell.run("echo ${u y}");
+        assert_eq!(outcome.stderr, "shell: ${u y}: bad substitution\n");
+        assert_eq!(outcome.status, 1);
+    }
+
+    #[test]
+    fn arrays() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run(
+            "a=(x 'y z'); a[3]=w; a+=(v); printf '[%s]' \"${a[@]}\" \"${a[*]}\" ${#a[@]} ${!a[@]}",
+        );
+        assert_eq!(outcome.stdout, "[x][y z][w][v][x y z w v][4][0][1][3][4]");
+
+        let outcome = shell.run("unset 'a[1]'; printf '[%s]' \"${a[@]:1}\" ${a[-1]} $a");
+        assert_eq!(outcome.stdout, "[w][v][v][x]");
+
+        let outcome = shell.run("declare -A m=([k]=1); m[\"two words\"]=2; declare -p m");
+        assert_eq!(
+            outcome.stdout,
+            "declare -A m=([k]=\"1\" [\"two words\"]=\"2\")\n"
+        );
+    }
+
+    #[test]
+    fn declare_attributes() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell
+            .run("declare -i n=2*3; n+=1; declare -u u=ab; declare -n r=u; r+=c; echo $n $u ${!r}");
+        assert_eq!(outcome.stdout, "7 ABC u\n");
+
+        let outcome = shell.run("readonly R=1; R=2; echo not reached");
+        assert_eq!(outcome.stderr, "shell: R: readonly variable\n");
+        assert_eq!((outcome.status, outcome.exited), (1, true));
+        assert_eq!(outcome.stdout, "");
+
+        let outcome = shell.run("declare +r R; unset R; declare -p R");
+        assert_eq!(outcome.stdout, "declare -r R=\"1\"\n");
+        assert_eq!(
+            outcome.stderr,
+            "declare: R: readonly variable\n\
+             unset: R: readonly variable\n"
+        );
+
+        let outcome = shell.run(
+            "v='(a b)'; declare b=\"$v\" c='(x)' d=(\"$v\"); readonly P=\"$v\"; \
+             export X=$v; declare -p b c d P X",
+        );
+        assert_eq!(
+            outcome.stdout,
+            "declare -- b=\"(a b)\"\n\
+             declare -- c=\"(x)\"\n\
+             declare -a d=([0]=\"(a b)\")\n\
+             declare -r P=\"(a b)\"\n\
+             declare -x X=\"(a b)\"\n"
+        );
+
+        let outcome = shell.run("builtin declare e=(1 2); command export f=(3); declare -p e f");
+        assert_eq!(
+            outcome.stdout,
+            "declare -a e=([0]=\"1\" [1]=\"2\")\n\
+             declare -ax f=([0]=\"3\")\n"
+        );
+
+        let outcome = shell.run("local x=1");
+        assert_eq!(outcome.stderr, "local: can only be used in a function\n");
+        assert_eq!(outcome.status, 1);
+
+        let outcome = shell.run("declare -F; declare -i i=9223372036854775807; i+=1; echo $i");
+        assert_eq!(outcome.stderr, "");
+        assert_eq!(outcome.status, 0);
+        assert_eq!(outcome.stdout, "-9223372036854775808\n");
+    }
+
+    #[test]
+    fn background_jobs_are_only_announced_interactively() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run("/bin/true &");
+        assert_eq!(outcome.stderr, "");
+
+        let mut shell = Shell::builder().interactive(true).build();
+        let outcome = shell.run("/bin/true &");
+        assert!(outcome.stderr.starts_with("[1] "));
+    }
+
+    #[test]
+    fn kill_builtin() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run("kill -l 130 TERM; kill -0 $$ && kill -s 0 -- $$ && echo alive");
+        assert_eq!(outcome.stdout, "INT\n15\nalive\n");
+
+        let outcome = shell.run("kill %1 abc; kill -NOPE 1");
+        assert_eq!(
+            outcome.stderr,
+            "kill: %1: no such job\n\
+             kill: abc: arguments must be process or job IDs\n\
+             kill: NOPE: invalid signal specification\n"
+        );
+        assert_eq!(outcome.status, 1);
+    }
+
+    /// Whether process `pid` has gone, or is only waiting to be reaped.
+    fn process_gone(pid: &str) -> bool {
+        (0..50).any(|_| {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid));
+            let gone = stat.map_or(true, |s| s.rsplit(')').next().unwrap().starts_with(" Z"));
+            if !gone {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            gone
+        })
+    }
+
+    #[test]
+    fn kill_reaches_the_commands_of_a_job() {
+        let mut shell = Shell::builder().var("PATH", "/bin:/usr/bin").build();
+        let path = std::env::temp_dir().join(format!("shell-job-{}", std::process::id()));
+        let outcome = shell.run(&format!(
+            "sh -c 'echo $$ > {0}; exec sleep 34' & sleep 0.2; kill %1; cat {0}",
+            path.display()
+        ));
+        std::fs::remove_file(&path).ok();
+        assert_eq!(outcome.status, 0);
+        assert!(process_gone(outcome.stdout.trim()));
+    }
+
+    #[test]
+    fn huponexit_reaches_the_commands_of_jobs() {
+        let mut shell = Shell::builder().var("PATH", "/bin:/usr/bin").build();
+        let path = std::env::temp_dir().join(format!("shell-hup-{}", std::process::id()));
+        let outcome = shell.run(&format!(
+            "set -o huponexit; sh -c 'echo $$ > {0}; exec sleep 30' & sleep 0.2; cat {0}",
+            path.display()
+        ));
+        std::fs::remove_file(&path).ok();
+        shell.shutdown();
+        assert!(process_gone(outcome.stdout.trim()));
+    }
+
+    #[test]
+    fn traps() {
+        let mut shell = Shell::builder().build();
+        let outcome = shell.run(
+            "(trap 'echo caught' USR1; /bin/sh -c 'kill -USR1 $PPID'; echo after)\n\
+             trap 'echo failed $?' ERR; false; false && true; trap - ERR; false",
+        );
+        assert_eq!(outcome.stdout, "caught\nafter\nfailed 1\n");
+
+        let outcome = shell.run("trap 'echo $BASH_COMMAND' DEBUG; echo a; trap DEBUG");
+        assert_eq!(outcome.stdout, "echo a\na\ntrap DEBUG\n");
+
+        let outcome = shell.run("trap 'echo bye $?' EXIT; trap -p; trap x BOGUS");
+        assert_eq!(outcome.stdout, "trap -- 'echo bye $?' EXIT\n");
+        assert_eq!(
+            outcome.stderr,
+            "trap: BOGUS: invalid signal specification\n"
+        );
+        let outcome =
+            shell.run("trap 'echo ret' RETURN; trap -p RETURN; trap - RETURN; trap -p RETURN");
+        assert_eq!(outcome.stdout, "trap -- 'echo ret' RETURN\n");
+        assert_eq!(outcome.status, 0);
+        let outcome = shell.capture(|shell| shell.execute("false").map(|_| shell.shutdown()));
+        assert_eq!(outcome.stdout, "bye 1\n");
+        assert_eq!(outcome.status, 1);
+    }
+
+    #[test]
+    fn ignoreeof_counts_ends_of_input() {
+        let mut shell = Shell::builder().interactive(true).build();
+        assert!(!shell.ignore_eof());
+
+        shell.set_var("IGNOREEOF", "2").unwrap();
+        assert!(shell.ignore_eof());
+        assert!(shell.ignore_eof());
+        assert!(!shell.ignore_eof());
+
+        shell.execute("false").unwrap();
+        assert!(shell.ignore_eof());
+        assert_eq!(shell.shutdown(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+/// A decoded backslash escape.
+#[derive(Debug, PartialEq)]
+pub enum Escape {
+    Bytes(Vec<u8>),
+    /// `\c`: produce no further output.
+    Stop,
+}
+
+/// Decodes the escape sequence at the start of `s`, which is the text
+/// following a backslash, returning it with the number of bytes of `s` it
+/// used. Unknown escapes give `None` and are left as written.
+///
+/// `echo` spells octal escapes `\0nnn`; `printf` formats use `\nnn` and
+/// also accept `\"` and `\'`.
+pub fn decode(s: &str, echo_octal: bool) -> Option<(Escape, usize)> {
+    let bytes = s.as_bytes();
+    let byte = |b: u8| Some((Escape::Bytes(vec![b]), 1));
+    match *bytes.first()? {
+        b'a' => byte(0x07),
+        b'b' => byte(0x08),
+        b'e' | b'E' => byte(0x1b),
+        b'f' => byte(0x0c),
+        b'n' => byte(b'\n'),
+        b'r' => byte(b'\r'),
+        b't' => byte(b'\t'),
+        b'v' => byte(0x0b),
+        b'\\' => byte(b'\\'),
+        b'"' | b'\'' if !echo_octal => byte(bytes[0]),
+        b'c' => Some((Escape::Stop, 1)),
+        b'0'..=b'7' => {
+            let skip = usize::from(echo_octal && bytes[0] == b'0');
+            if echo_octal && skip == 0 {
+                return None;
+            }
+            let digits = digits_at(&s[skip..], 3, 8);
+            let value = u32::from_str_radix(&s[skip..skip + digits], 8).unwrap_or(0);
+            Some((Escape::Bytes(vec![value as u8]), skip + digits))
+        }
+        b'x' => {
+            let digits = digits_at(&s[1..], 2, 16);
+            let value = u8::from_str_radix(s.get(1..1 + digits)?, 16).ok()?;
+            Some((Escape::Bytes(vec![value]), 1 + digits))
+        }
+        b'u' | b'U' => {
+            let max = if bytes[0] == b'u' { 4 } else { 8 };
+            let digits = digits_at(&s[1..], max, 16);
+            let value = u32::from_str_radix(s.get(1..1 + digits)?, 16).ok()?;
+            let c = char::from_u32(value)?;
+            Some((Escape::Bytes(c.to_string().into_bytes()), 1 + digits))
+        }
+        _ => None,
+    }
+}
+
+/// How many of the first `max` characters of `s` are digits in `radix`.
+fn digits_at(s: &str, max: usize, radix: u32) -> usize {
+    s.chars()
+        .take(max)
+        .take_while(|c| c.is_digit(radix))
+        .count()
+}
+
+/// Replaces the escapes in `s`, returning the bytes and whether a `\c`
+/// cut the output short.
+pub fn unescape(s: &str, echo_octal: bool) -> (Vec<u8>, bool) {
+    let mut out = vec![];
+    let mut i = 0;
+    while let Some(pos) = s[i..].find('\\') {
+        out.extend_from_slice(&s.as_bytes()[i..i + pos]);
+        i += pos + 1;
+        match decode(&s[i..], echo_octal) {
+            Some((Escape::Bytes(b), used)) => {
+                out.extend(b);
+                i += used;
+            }
+            Some((Escape::Stop, _)) => return (out, true),
+            None => out.push(b'\\'),
+        }
+    }
+    out.extend_from_slice(&s.as_bytes()[i..]);
+    (out, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn echo_and_printf_octal() {
+        assert_eq!(
+            unescape("a\\tb\\0101\\x41\\u00e9", true).0,
+            "a\tbAAé".as_bytes()
+        );
+        assert_eq!(unescape("\\101\\q", true).0, b"\\101\\q");
+        assert_eq!(unescape("\\101\\'", false).0, b"A'");
+        assert_eq!(unescape("one\\ctwo", true), (b"one".to_vec(), true));
+    }
+}
@@ -0,0 +1,34 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Walks up from `start` looking for a `.git` directory (or a `.git` file
+/// pointing elsewhere, as used by worktrees and submodules).
+pub fn find_git_dir(start: &Path) -> Option<PathBuf> {
+    let mut dir = Some(start);
+    while let Some(d) = dir {
+        let cand = d.join(".git");
+        if cand.is_dir() {
+            return Some(cand);
+        }
+        if cand.is_file() {
+            let contents = fs::read_to_string(&cand).ok()?;
+            let target = contents.trim().strip_prefix("gitdir:")?.trim();
+            return Some(d.join(target));
+        }
+        dir = d.parent();
+    }
+    None
+}
+
+/// Returns the checked out branch for the repository containing `start`,
+/// or the abbreviated commit id when HEAD is detached.
+pub fn current_branch(start: &Path) -> Option<String> {
+    let head = fs::read_to_string(find_git_dir(start)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    if let Some(reference) = head.strip_prefix("ref:") {
+        let reference = reference.trim();
+        let branch = reference.strip_prefix("refs/heads/").unwrap_or(reference);
+        return Some(branch.to_string());
+    }
+    Some(head.chars().take(7).collect())
+}
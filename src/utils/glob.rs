@@ -0,0 +1,234 @@
+use std::fs;
+use std::path::Path;
+
+/// Whether `pattern` contains an unescaped `*`, `?` or `[`.
+pub fn has_glob_chars(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Backslash-escapes the pattern characters in `s` so it only matches
+/// itself.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Matches `text` against a shell pattern supporting `*`, `?`, bracket
+/// expressions (with `!`/`^` negation, ranges and `[:class:]`) and
+/// backslash escapes.
+pub fn pattern_matches(pattern: &str, text: &str) -> bool {
+    let pat: Vec<char> = pattern.chars().collect();
+    let txt: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the most recent `*` and the text index it matched up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < txt.len() {
+        let step = match pat.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match match_bracket(&pat, p, txt[t]) {
+                Some((true, len)) => Some(len),
+                Some((false, _)) => None,
+                None if txt[t] == '[' => Some(1),
+                None => None,
+            },
+            Some('\\') if p + 1 < pat.len() => (pat[p + 1] == txt[t]).then_some(2),
+            Some(&c) => (c == txt[t]).then_some(1),
+            None => None,
+        };
+        match step {
+            Some(len) => {
+                p += len;
+                t += 1;
+            }
+            None => match backtrack {
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pat[p..].iter().all(|&c| c == '*')
+}
+
+/// Matches `c` against the bracket expression starting at `pat[start]`.
+/// Returns whether it matched and the length of the expression, or `None`
+/// when the bracket is unterminated and should be taken literally.
+fn match_bracket(pat: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(pat.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let cur = *pat.get(i)?;
+        if cur == ']' && !first {
+            break;
+        }
+        first = false;
+        if cur == '[' && pat.get(i + 1) == Some(&':') {
+            let rest: String = pat[i + 2..].iter().collect();
+            if let Some(end) = rest.find(":]") {
+                matched |= class_matches(&rest[..end], c);
+                i += 2 + rest[..end].chars().count() + 2;
+                continue;
+            }
+        }
+        let (lo, next) = match cur {
+            '\\' => (*pat.get(i + 1)?, i + 2),
+            other => (other, i + 1),
+        };
+        if pat.get(next) == Some(&'-') && pat.get(next + 1).is_some_and(|&h| h != ']') {
+            let (hi, after) = match pat[next + 1] {
+                '\\' => (*pat.get(next + 2)?, next + 3),
+                other => (other, next + 2),
+            };
+            matched |= lo <= c && c <= hi;
+            i = after;
+        } else {
+            matched |= lo == c;
+            i = next;
+        }
+    }
+    Some((matched != negated, i + 1 - start))
+}
+
+fn class_matches(class: &str, c: char) -> bool {
+    match class {
+        "alpha" => c.is_alphabetic(),
+        "digit" => c.is_ascii_digit(),
+        "alnum" => c.is_alphanumeric(),
+        "upper" => c.is_uppercase(),
+        "lower" => c.is_lowercase(),
+        "space" => c.is_whitespace(),
+        "blank" => c == ' ' || c == '\t',
+        "punct" => c.is_ascii_punctuation(),
+        "xdigit" => c.is_ascii_hexdigit(),
+        "cntrl" => c.is_control(),
+        "print" => !c.is_control(),
+        "graph" => !c.is_control() && !c.is_whitespace(),
+        _ => false,
+    }
+}
+
+/// Expands a pathname pattern against the filesystem, returning the sorted
+/// matches. Hidden entries only match components that start with `.`.
+pub fn expand(pattern: &str) -> Vec<String> {
+    let (mut paths, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (vec![String::from("/")], rest),
+        None => (vec![String::new()], pattern),
+    };
+    let components: Vec<&str> = rest.split('/').collect();
+
+    for (idx, component) in components.iter().enumerate() {
+        let last = idx + 1 == components.len();
+        let mut next = vec![];
+        for base in &paths {
+            if component.is_empty() {
+                // Consecutive or trailing slashes only match directories.
+                if Path::new(base).is_dir() {
+                    next.push(join(base, "", last));
+                }
+                continue;
+            }
+            if !has_glob_chars(component) {
+                next.push(join(base, &unescape(component), last));
+                continue;
+            }
+            let dir = if base.is_empty() { "." } else { base.as_str() };
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|name| !name.starts_with('.') || component.starts_with('.'))
+                .filter(|name| pattern_matches(component, name))
+                .collect();
+            names.sort();
+            next.extend(names.iter().map(|name| join(base, name, last)));
+        }
+        paths = next;
+    }
+    paths.retain(|p| Path::new(p).symlink_metadata().is_ok());
+    paths
+}
+
+fn join(base: &str, name: &str, last: bool) -> String {
+    let sep = if last { "" } else { "/" };
+    if base.is_empty() || base.ends_with('/') {
+        format!("{}{}{}", base, name, sep)
+    } else {
+        format!("{}/{}{}", base, name, sep)
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(pattern_matches("*.rs", "main.rs"));
+        assert!(!pattern_matches("*.rs", "main.rc"));
+        assert!(pattern_matches("a*b*c", "axxbyybc"));
+        assert!(pattern_matches("?at", "cat"));
+        assert!(!pattern_matches("?at", "at"));
+        assert!(pattern_matches("*", ""));
+    }
+
+    #[test]
+    fn brackets() {
+        assert!(pattern_matches("[a-c]x", "bx"));
+        assert!(!pattern_matches("[!a-c]x", "bx"));
+        assert!(pattern_matches("[]]", "]"));
+        assert!(pattern_matches("[[:digit:]]*", "7up"));
+        assert!(pattern_matches("[abc", "[abc"));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(pattern_matches("\\*", "*"));
+        assert!(!pattern_matches("\\*", "x"));
+        assert!(pattern_matches(&escape("a[1]*"), "a[1]*"));
+        assert!(!has_glob_chars("a\\*b"));
+    }
+}
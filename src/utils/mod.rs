@@ -1 +1,10 @@
+pub mod escape;
+pub mod git;
+pub mod glob;
 pub mod path_lookup;
+pub mod paths;
+pub mod process;
+pub mod quote;
+pub mod regex;
+pub mod signal;
+pub mod sys;
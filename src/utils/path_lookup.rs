@@ -1,16 +1,16 @@
-use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub fn find_in_path(cmd: &str) -> Option<PathBuf> {
+/// Searches the colon-separated directories of `path_var` for an executable
+/// named `cmd`. A `cmd` containing a slash is checked as given.
+pub fn find_in_path(cmd: &str, path_var: &str) -> Option<PathBuf> {
     let path = Path::new(cmd);
     if path.components().count() > 1 {
-        if is_executable(&path) {
+        if is_executable(path) {
             return Some(path.to_path_buf());
         }
         return None;
     }
-    let path_var = env::var("PATH").unwrap_or_default();
     for dir in path_var.split(':') {
         let cand = Path::new(dir).join(cmd);
         if is_executable(&cand) {
@@ -20,13 +20,26 @@ pub fn find_in_path(cmd: &str) -> Option<PathBuf> {
     None
 }
 
+/// Every executable named `cmd` in the directories of `path_var`, in
+/// search order.
+pub fn find_all_in_path(cmd: &str, path_var: &str) -> Vec<PathBuf> {
+    if cmd.contains('/') {
+        return find_in_path(cmd, path_var).into_iter().collect();
+    }
+    path_var
+        .split(':')
+        .map(|dir| Path::new(dir).join(cmd))
+        .filter(|cand| is_executable(cand))
+        .collect()
+}
+
 pub fn is_executable(path: &Path) -> bool {
     if let Ok(meta) = fs::metadata(path) {
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
             let mode = meta.permissions().mode();
-            (mode & 0o111) != 0
+            meta.is_file() && (mode & 0o111) != 0
         }
         #[cfg(not(unix))]
         {
@@ -37,22 +50,3 @@ pub fn is_executable(path: &Path) -> bool {
         false
     }
 }
-
-pub fn expand_tilde(path: &str) -> String {
-    if !path.starts_with('~') {
-        return path.to_string();
-    }
-    let home = env::var("HOME").unwrap_or_else(|_| "/".to_string());
-
-    if path == "~" {
-        return home;
-    }
-
-    if let Some(rest) = path.strip_prefix("~/") {
-        let mut pb = PathBuf::from(home);
-        pb.push(rest);
-        return pb.to_string_lossy().to_string();
-    }
-
-    path.to_string()
-}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path};
+
+/// Joins a relative `target` onto `base` and removes `.` and `..`
+/// components lexically, without looking at symbolic links, as `cd -L`
+/// does.
+pub fn logical_join(base: &str, target: &str) -> String {
+    let joined = if target.starts_with('/') {
+        target.to_string()
+    } else {
+        format!("{}/{}", base, target)
+    };
+    let mut parts: Vec<&str> = vec![];
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            p => parts.push(p),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Whether `pwd` is an absolute path free of `.` and `..` that names the
+/// current directory, so an inherited `PWD` can be trusted.
+pub fn is_valid_pwd(pwd: &str) -> bool {
+    let path = Path::new(pwd);
+    if !path.is_absolute() {
+        return false;
+    }
+    if path
+        .components()
+        .any(|c| matches!(c, Component::CurDir | Component::ParentDir))
+    {
+        return false;
+    }
+    match (fs::metadata(path), fs::metadata(".")) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logical_join_is_lexical() {
+        assert_eq!(logical_join("/a/link", ".."), "/a");
+        assert_eq!(logical_join("/a/b", "../c/./d/"), "/a/c/d");
+        assert_eq!(logical_join("/a", "/x/../y"), "/y");
+        assert_eq!(logical_join("/", "../.."), "/");
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::unix::io::FromRawFd;
+
+pub enum Fork {
+    Child,
+    Parent(libc::pid_t),
+}
+
+/// Forks the shell, flushing buffered output first so it is not written
+/// twice.
+pub fn fork() -> io::Result<Fork> {
+    io::stdout().flush().ok();
+    io::stderr().flush().ok();
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(Fork::Child),
+        pid => Ok(Fork::Parent(pid)),
+    }
+}
+
+/// Terminates a forked child without running the parent's cleanup.
+pub fn exit_child(status: i32) -> ! {
+    io::stdout().flush().ok();
+    io::stderr().flush().ok();
+    unsafe { libc::_exit(status) }
+}
+
+/// Moves `pid` (0 for this process) into a new process group of its own,
+/// whose ID is its pid. Both a forked child and its parent call this, so
+/// that the group exists whichever runs first.
+pub fn new_process_group(pid: libc::pid_t) {
+    unsafe { libc::setpgid(pid, pid) };
+}
+
+pub fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe { Ok((File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1]))) }
+}
+
+/// Blocks until `pid` terminates and returns its shell exit status.
+pub fn wait_pid(pid: libc::pid_t) -> i32 {
+    let mut status = 0;
+    loop {
+        if unsafe { libc::waitpid(pid, &mut status, 0) } >= 0 {
+            return decode_status(status);
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return 127;
+        }
+    }
+}
+
+/// Returns the exit status of `pid` if it has terminated, without blocking.
+pub fn try_wait_pid(pid: libc::pid_t) -> Option<i32> {
+    let mut status = 0;
+    match unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } {
+        0 => None,
+        -1 => Some(127),
+        _ => Some(decode_status(status)),
+    }
+}
+
+/// Maps a raw wait status to a shell status, using `128 + N` for children
+/// killed by signal N.
+fn decode_status(status: i32) -> i32 {
+    if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else {
+        1
+    }
+}
@@ -0,0 +1,113 @@
+use std::ffi::{CStr, CString};
+
+/// A compiled POSIX extended regular expression.
+pub struct Regex {
+    re: libc::regex_t,
+    groups: usize,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let pat = CString::new(pattern).map_err(|_| format!("{}: invalid pattern", pattern))?;
+        let mut re: libc::regex_t = unsafe { std::mem::zeroed() };
+        let rc = unsafe { libc::regcomp(&mut re, pat.as_ptr(), libc::REG_EXTENDED) };
+        if rc != 0 {
+            let mut buf = [0 as libc::c_char; 256];
+            unsafe { libc::regerror(rc, &re, buf.as_mut_ptr(), buf.len()) };
+            let msg = unsafe { CStr::from_ptr(buf.as_ptr()) };
+            return Err(msg.to_string_lossy().into_owned());
+        }
+        Ok(Regex {
+            re,
+            groups: count_groups(pattern),
+        })
+    }
+
+    /// Matches `text` anywhere, returning the text of the whole match
+    /// followed by that of each group. Groups that took no part in the
+    /// match are empty.
+    pub fn captures(&self, text: &str) -> Option<Vec<String>> {
+        let txt = CString::new(text).ok()?;
+        let mut matches = vec![
+            libc::regmatch_t {
+                rm_so: -1,
+                rm_eo: -1
+            };
+            self.groups + 1
+        ];
+        let rc = unsafe {
+            libc::regexec(
+                &self.re,
+                txt.as_ptr(),
+                matches.len(),
+                matches.as_mut_ptr(),
+                0,
+            )
+        };
+        if rc != 0 {
+            return None;
+        }
+        let groups = matches
+            .iter()
+            .map(
+                |m| match (usize::try_from(m.rm_so), usize::try_from(m.rm_eo)) {
+                    (Ok(start), Ok(end)) => {
+                        text.get(start..end).map(str::to_string).unwrap_or_default()
+                    }
+                    _ => String::new(),
+                },
+            )
+            .collect();
+        Some(groups)
+    }
+}
+
+/// The number of parenthesized groups in an extended regular expression,
+/// which `regex_t` keeps in a private field.
+fn count_groups(pattern: &str) -> usize {
+    let mut groups = 0;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '(' => groups += 1,
+            '[' => {
+                // `]` right after `[` or `[^` is a literal member.
+                chars.next_if_eq(&'^');
+                chars.next_if_eq(&']');
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    groups
+}
+
+impl Drop for Regex {
+    fn drop(&mut self) {
+        unsafe { libc::regfree(&mut self.re) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups() {
+        let re = Regex::new("([a-z]+)-([0-9]+)?x").unwrap();
+        assert_eq!(
+            re.captures("id: abc-x"),
+            Some(vec!["abc-x".into(), "abc".into(), "".into()])
+        );
+        assert_eq!(re.captures("ABC"), None);
+        assert!(Regex::new("(").is_err());
+        assert_eq!(count_groups(r"(a)\\([(]|[]()])"), 2);
+    }
+}
@@ -0,0 +1,181 @@
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The signals the shell knows by name, without the `SIG` prefix.
+const SIGNALS: &[(&str, libc::c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
+/// The named signals ordered by number.
+pub fn all() -> Vec<(&'static str, libc::c_int)> {
+    let mut signals = SIGNALS.to_vec();
+    signals.sort_by_key(|&(_, n)| n);
+    signals
+}
+
+/// The name of signal `n` without the `SIG` prefix.
+pub fn name(n: libc::c_int) -> Option<&'static str> {
+    SIGNALS
+        .iter()
+        .find(|&&(_, s)| s == n)
+        .map(|&(name, _)| name)
+}
+
+/// The number of a signal given by name, with or without `SIG` and in any
+/// case, or by number. `0` is accepted, as it only checks that a process
+/// exists.
+pub fn number(spec: &str) -> Option<libc::c_int> {
+    if let Ok(n) = spec.parse::<libc::c_int>() {
+        return (n == 0 || name(n).is_some()).then_some(n);
+    }
+    let upper = spec.to_ascii_uppercase();
+    let bare = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS
+        .iter()
+        .find(|&&(name, _)| name == bare)
+        .map(|&(_, n)| n)
+}
+
+/// `kill -l` and `trap -l`: every named signal, five to a line.
+pub fn listing() -> Vec<String> {
+    let entries: Vec<String> = all()
+        .iter()
+        .map(|(name, n)| format!("{:2}) SIG{}", n, name))
+        .collect();
+    entries.chunks(5).map(|row| row.join("\t")).collect()
+}
+
+/// What a process does when it receives a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+    Default,
+    Ignore,
+    /// Record it for `pending` to return.
+    Catch,
+}
+
+/// The pipe that caught signals are written to, read end first. Writing
+/// a byte is all that is safe to do in a handler; the shell reads them
+/// back between commands.
+static PIPE: [AtomicI32; 2] = [AtomicI32::new(-1), AtomicI32::new(-1)];
+
+extern "C" fn record(sig: libc::c_int) {
+    let fd = PIPE[1].load(Ordering::Relaxed);
+    let byte = sig as u8;
+    unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+}
+
+/// Opens the signal pipe if it is not open. Neither end blocks, and
+/// neither is passed on to programs the shell runs.
+fn open_pipe() -> io::Result<()> {
+    if PIPE[0].load(Ordering::Relaxed) >= 0 {
+        return Ok(());
+    }
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    for fd in fds {
+        unsafe {
+            libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK);
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    PIPE[1].store(fds[1], Ordering::Relaxed);
+    PIPE[0].store(fds[0], Ordering::Relaxed);
+    Ok(())
+}
+
+/// Closes the signal pipe, so that a forked child opens its own rather
+/// than reading or writing its parent's.
+pub fn close_pipe() {
+    for end in &PIPE {
+        let fd = end.swap(-1, Ordering::Relaxed);
+        if fd >= 0 {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// Sets what the process does with signal `sig`. Ignored signals stay
+/// ignored in programs the shell runs; caught ones go back to the default.
+pub fn set_disposition(sig: libc::c_int, disposition: Disposition) -> io::Result<()> {
+    let handler = match disposition {
+        Disposition::Default => libc::SIG_DFL,
+        Disposition::Ignore => libc::SIG_IGN,
+        Disposition::Catch => {
+            open_pipe()?;
+            record as extern "C" fn(libc::c_int) as libc::sighandler_t
+        }
+    };
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = handler;
+    action.sa_flags = libc::SA_RESTART;
+    unsafe { libc::sigemptyset(&mut action.sa_mask) };
+    if unsafe { libc::sigaction(sig, &action, std::ptr::null_mut()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The caught signals not yet returned, in the order they arrived.
+pub fn pending() -> Vec<libc::c_int> {
+    let fd = PIPE[0].load(Ordering::Relaxed);
+    let mut signals = vec![];
+    if fd < 0 {
+        return signals;
+    }
+    let mut buf = [0u8; 64];
+    loop {
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n <= 0 {
+            return signals;
+        }
+        signals.extend(buf[..n as usize].iter().map(|&b| libc::c_int::from(b)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_numbers() {
+        assert_eq!(number("TERM"), Some(libc::SIGTERM));
+        assert_eq!(number("sigkill"), Some(libc::SIGKILL));
+        assert_eq!(number("9"), Some(9));
+        assert_eq!(number("0"), Some(0));
+        assert_eq!(number("SIGNOPE"), None);
+        assert_eq!(number("-1"), None);
+        assert_eq!(name(libc::SIGINT), Some("INT"));
+        assert_eq!(all().first(), Some(&("HUP", 1)));
+    }
+}